@@ -0,0 +1,70 @@
+// Times primary rays traced one at a time against 8-wide packets.
+//
+//     cargo bench --bench packets
+
+use std::{f64::consts::PI, time::Instant};
+
+use ray_tracer_challenge::{
+    features::packets::{hit_world_packet, RayPacket},
+    prelude::*,
+};
+
+fn scene() -> ray_tracer_challenge::Result<(World, Camera)> {
+    let mut sphere = Shape::Sphere(Sphere::new());
+    sphere.set_transform(translation(-1.0, 1.0, 0.0))?;
+    let mut cube = Shape::Cube(Cube::new());
+    cube.set_transform(translation(1.5, 0.5, 1.0) * rotation_y(0.6) * scaling(0.5, 0.5, 0.5))?;
+    let mut quad = Shape::Quad(Quad::new());
+    quad.set_transform(translation(0.0, 2.5, 2.0) * scaling(3.0, 1.0, 1.0))?;
+    let shapes = [Shape::Plane(Plane::new()), sphere, cube, quad];
+    let world = World::new(
+        Light::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)),
+        &shapes,
+    );
+    let mut camera = Camera::new(256.0, 128.0, PI / 3.0)?;
+    camera.set_transform(view_transformation(
+        Point3::new(0.0, 1.5, -6.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    ))?;
+    Ok((world, camera))
+}
+
+fn main() -> ray_tracer_challenge::Result<()> {
+    let (world, camera) = scene()?;
+    let (width, height) = (camera.hsize as usize, camera.vsize as usize);
+    let rounds = 20;
+
+    let start = Instant::now();
+    let mut scalar_hits = 0;
+    for _ in 0..rounds {
+        for y in 0..height {
+            for x in 0..width {
+                let r = camera.ray_for_pixel(x as f64, y as f64);
+                scalar_hits += world.closest_hit(&r, 0.0, f64::INFINITY).is_some() as usize;
+            }
+        }
+    }
+    let scalar = start.elapsed();
+
+    let start = Instant::now();
+    let mut packet_hits = 0;
+    for _ in 0..rounds {
+        for y in 0..height {
+            for x in (0..width).step_by(8) {
+                let rays: [Ray; 8] =
+                    std::array::from_fn(|i| camera.ray_for_pixel((x + i) as f64, y as f64));
+                packet_hits += hit_world_packet(&world, &RayPacket::from_rays(&rays))
+                    .iter()
+                    .filter(|h| h.is_some())
+                    .count();
+            }
+        }
+    }
+    let packet = start.elapsed();
+
+    assert_eq!(scalar_hits, packet_hits);
+    println!("{rounds} rounds of {width}x{height} primary rays");
+    println!("scalar: {scalar:?}, 8-wide packets: {packet:?}");
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::f64::consts::PI;
+
+use ray_tracer_challenge::prelude::*;
+
+fn main() -> ray_tracer_challenge::Result<()> {
+    let light_position = Point3::new(-8.0, 4.0, -8.0);
+    let light_color = Color::new(1.0, 1.0, 1.0);
+    let light = Light::new(light_position, light_color);
+
+    let mut floor = Plane::new();
+    floor.material.pattern = Some(Pattern::Checker(Checker::new(
+        Color::new(0.0, 0.0, 0.0),
+        Color::new(1.0, 1.0, 1.0),
+    )));
+    let mut left_wall = Plane::new();
+    left_wall
+        .set_transform(translation(0.0, 0.0, 8.0) * rotation_y(PI / 5.0) * rotation_x(PI / 2.0))?;
+    left_wall.material.color = Color::new(0.5, 0.5, 0.5);
+    let mut ceiling = Plane::new();
+    ceiling.set_transform(translation(0.0, 5.0, 0.0))?;
+    ceiling.material.color = Color::new(0.7, 0.8, 0.4);
+    let mut right_wall = Plane::new();
+    right_wall
+        .set_transform(translation(0.0, 0.0, 8.0) * rotation_y(-PI / 5.0) * rotation_x(PI / 2.0))?;
+    right_wall.material.color = Color::new(0.75, 0.75, 0.75);
+    let mut middle = Sphere::new();
+    middle.set_transform(translation(2.0, 2.0, 2.5) * scaling(2.0, 2.0, 2.0))?;
+    middle.material = Material::new();
+    // middle.material.pattern = Some(Pattern::Gradient(Gradient::new(
+    //     Color::new(0.0, 0.0, 0.0),
+    //     Color::new(1.0, 1.0, 1.0),
+    // )));
+    // middle.material.color = Color::new(0.1, 1.0, 0.5);
+    middle.material.reflective = 0.5;
+    middle.material.diffuse = 0.7;
+    middle.material.specular = 0.3;
+
+    let mut right = Sphere::new();
+    right.set_transform(translation(-2.5, 1.5, 4.0) * scaling(1.5, 1.5, 1.5))?;
+    right.material = Material::new();
+    right.material.reflective = 0.8;
+    right.material.color = Color::new(0.7, 0.8, 0.4);
+    // right.material.pattern = Some(Pattern::Checker(Checker::new(
+    //     Color::new(1.0, 1.0, 1.0),
+    //     Color::new(0.0, 0.0, 0.0),
+    // )));
+    // right.material.color = Color::new(0.5, 1.0, 0.1);
+    right.material.diffuse = 0.7;
+    right.material.specular = 0.3;
+
+    let mut left = Sphere::new();
+    left.set_transform(translation(-2.5, 0.33, 0.75) * scaling(0.33, 0.33, 0.33))?;
+    left.material = Material::new();
+    left.material.color = Color::new(1.0, 0.8, 0.1);
+    // left.material.reflective = 0.3;
+    left.material.diffuse = 0.7;
+    left.material.specular = 0.3;
+
+    let mut cube = Cube::new();
+    cube.set_transform(translation(0.0, 2.0, 0.0))?;
+    cube.material.color = Color::new(1.0, 1.0, 1.0);
+    // cube.material.reflective = 0.8;
+    let world = World::new(
+        light.clone(),
+        &[
+            Shape::Plane(left_wall),
+            Shape::Plane(right_wall),
+            // Shape::Plane(ceiling),
+            Shape::Plane(floor),
+            // Shape::Sphere(middle),
+            // Shape::Sphere(right),
+            // Shape::Sphere(left),
+            Shape::Cube(cube),
+        ],
+    );
+    let mut camera = Camera::new(800.0, 400.0, PI / 2.0)?;
+    // let mut camera = Camera::new(140.0, 70.0, PI / 2.0);
+
+    camera.set_transform(view_transformation(
+        Point3::new(-4.0, 5.0, -8.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.0, 2.0, 0.0),
+    ))?;
+    camera.render(&world).to_ppm();
+    Ok(())
+}
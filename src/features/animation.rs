@@ -0,0 +1,495 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    camera::cameras::Camera,
+    canvas::Canvas,
+    error::{Error, Result},
+    matrice::Matrice4,
+    simd::{F64x4, Simd},
+    transformations::{
+        rotation_x, rotation_y, rotation_z, scaling, translation, view_transformation,
+    },
+    tuple::{Color, Point3, Vector3},
+    world::{ShapeId, World},
+};
+
+// Values that can be keyframed. Both interpolation modes reduce to a weighted
+// sum of the four keys around the current time.
+pub trait Keyframe: Clone {
+    fn combine(keys: [&Self; 4], weights: [f64; 4]) -> Self;
+}
+
+impl Keyframe for f64 {
+    fn combine(keys: [&Self; 4], weights: [f64; 4]) -> Self {
+        keys.iter().zip(weights).map(|(k, w)| *k * w).sum()
+    }
+}
+
+// the weights of both modes sum to one, so points blend as an affine
+// combination even though they cannot be added
+fn blend(lanes: [F64x4; 4], weights: [f64; 4]) -> F64x4 {
+    lanes
+        .iter()
+        .zip(weights)
+        .fold(Simd::splat(0.0), |acc, (l, w)| acc + *l * w)
+}
+
+impl Keyframe for Point3 {
+    fn combine(keys: [&Self; 4], weights: [f64; 4]) -> Self {
+        Point3::from_lanes(blend(keys.map(Point3::lanes), weights))
+    }
+}
+
+impl Keyframe for Vector3 {
+    fn combine(keys: [&Self; 4], weights: [f64; 4]) -> Self {
+        Vector3::from_lanes(blend(keys.map(Vector3::lanes), weights))
+    }
+}
+
+impl Keyframe for Color {
+    fn combine(keys: [&Self; 4], weights: [f64; 4]) -> Self {
+        Color::from_lanes(blend(keys.map(Color::lanes), weights))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    // Catmull-Rom spline, passes through every key with a smooth tangent
+    CatmullRom,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Track<T: Keyframe> {
+    keys: Vec<(f64, T)>,
+    pub interpolation: Interpolation,
+}
+
+impl<T: Keyframe> Track<T> {
+    // fails without any keys, since there would be nothing to hold
+    pub fn new(mut keys: Vec<(f64, T)>, interpolation: Interpolation) -> Result<Self> {
+        if keys.is_empty() {
+            return Err(Error::EmptyTrack);
+        }
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self {
+            keys,
+            interpolation,
+        })
+    }
+
+    pub fn linear(keys: Vec<(f64, T)>) -> Result<Self> {
+        Self::new(keys, Interpolation::Linear)
+    }
+
+    pub fn spline(keys: Vec<(f64, T)>) -> Result<Self> {
+        Self::new(keys, Interpolation::CatmullRom)
+    }
+
+    // a track that holds the same value at all times
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    // the value at `time`, held at the first and last keys outside their
+    // range; a NaN time holds the first key
+    pub fn at(&self, time: f64) -> T {
+        let last = self.keys.len() - 1;
+        if time.is_nan() || time <= self.keys[0].0 {
+            return self.keys[0].1.clone();
+        }
+        if time >= self.keys[last].0 {
+            return self.keys[last].1.clone();
+        }
+        let i = self.keys.partition_point(|k| k.0 <= time) - 1;
+        let (t0, t1) = (self.keys[i].0, self.keys[i + 1].0);
+        let span = t1 - t0;
+        let t = (time - t0) / span;
+        let weights = match self.interpolation {
+            Interpolation::Linear => [0.0, 1.0 - t, t, 0.0],
+            Interpolation::CatmullRom => {
+                // Hermite basis with each key's tangent taken over the time
+                // between its neighbours, so unevenly spaced keys do not
+                // overshoot; the end keys are repeated one span further out
+                let before = if i == 0 {
+                    2.0 * span
+                } else {
+                    t1 - self.keys[i - 1].0
+                };
+                let after = if i + 1 == last {
+                    2.0 * span
+                } else {
+                    self.keys[i + 2].0 - t0
+                };
+                let (a, b) = (span / before, span / after);
+                let (t2, t3) = (t * t, t * t * t);
+                let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+                let h10 = t3 - 2.0 * t2 + t;
+                let h01 = -2.0 * t3 + 3.0 * t2;
+                let h11 = t3 - t2;
+                [-h10 * a, h00 - h11 * b, h01 + h10 * a, h11 * b]
+            }
+        };
+        // the end keys are repeated to give the outer segments a neighbour
+        let key = |j: usize| &self.keys[j.min(last)].1;
+        T::combine(
+            [key(i.saturating_sub(1)), key(i), key(i + 1), key(i + 2)],
+            weights,
+        )
+    }
+}
+
+// camera placement keyed by the arguments of `view_transformation`
+#[derive(Clone, PartialEq, Debug)]
+pub struct CameraTrack {
+    pub from: Track<Point3>,
+    pub to: Track<Point3>,
+    pub up: Track<Vector3>,
+}
+
+impl CameraTrack {
+    pub fn new(from: Track<Point3>, to: Track<Point3>, up: Track<Vector3>) -> Self {
+        Self { from, to, up }
+    }
+
+    pub fn at(&self, time: f64) -> Matrice4 {
+        view_transformation(self.from.at(time), self.to.at(time), self.up.at(time))
+    }
+}
+
+// a shape transform split into translation, rotation (euler angles in
+// radians, applied x then y then z) and scale so each can be keyed apart
+#[derive(Clone, PartialEq, Debug)]
+pub struct TransformTrack {
+    pub shape: ShapeId,
+    pub translation: Track<Vector3>,
+    pub rotation: Track<Vector3>,
+    pub scale: Track<Vector3>,
+}
+
+impl TransformTrack {
+    // starts out as the identity for the world shape `shape`
+    pub fn new(shape: ShapeId) -> Self {
+        Self {
+            shape,
+            translation: Track::constant(Vector3::new(0.0, 0.0, 0.0)),
+            rotation: Track::constant(Vector3::new(0.0, 0.0, 0.0)),
+            scale: Track::constant(Vector3::new(1.0, 1.0, 1.0)),
+        }
+    }
+
+    pub fn at(&self, time: f64) -> Matrice4 {
+        let t = self.translation.at(time);
+        let r = self.rotation.at(time);
+        let s = self.scale.at(time);
+        translation(t.x, t.y, t.z)
+            * rotation_z(r.z)
+            * rotation_y(r.y)
+            * rotation_x(r.x)
+            * scaling(s.x, s.y, s.z)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MaterialTrack {
+    Color(ShapeId, Track<Color>),
+    Ambient(ShapeId, Track<f64>),
+    Diffuse(ShapeId, Track<f64>),
+    Specular(ShapeId, Track<f64>),
+    Shininess(ShapeId, Track<f64>),
+    Reflective(ShapeId, Track<f64>),
+    Transparency(ShapeId, Track<f64>),
+    Emissive(ShapeId, Track<Color>),
+    EmissiveStrength(ShapeId, Track<f64>),
+}
+
+impl MaterialTrack {
+    fn apply(&self, time: f64, world: &mut World) {
+        let shape = match self {
+            MaterialTrack::Color(s, _)
+            | MaterialTrack::Ambient(s, _)
+            | MaterialTrack::Diffuse(s, _)
+            | MaterialTrack::Specular(s, _)
+            | MaterialTrack::Shininess(s, _)
+            | MaterialTrack::Reflective(s, _)
+            | MaterialTrack::Transparency(s, _)
+            | MaterialTrack::Emissive(s, _)
+            | MaterialTrack::EmissiveStrength(s, _) => world.shape_mut(*s),
+        };
+        let mut m = shape.material();
+        match self {
+            MaterialTrack::Color(_, t) => m.color = t.at(time),
+            MaterialTrack::Ambient(_, t) => m.ambient = t.at(time),
+            MaterialTrack::Diffuse(_, t) => m.diffuse = t.at(time),
+            MaterialTrack::Specular(_, t) => m.specular = t.at(time),
+            MaterialTrack::Shininess(_, t) => m.shininess = t.at(time),
+            MaterialTrack::Reflective(_, t) => m.reflective = t.at(time),
+            MaterialTrack::Transparency(_, t) => m.transparency = t.at(time),
+            MaterialTrack::Emissive(_, t) => m.emissive = t.at(time),
+            MaterialTrack::EmissiveStrength(_, t) => m.emissive_strength = t.at(time),
+        }
+        shape.set_material(m);
+    }
+}
+
+// Everything that changes over a shot. Times are in seconds; parts without a
+// track keep whatever the camera and world were set up with.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Animation {
+    pub camera: Option<CameraTrack>,
+    pub light_position: Option<Track<Point3>>,
+    pub light_intensity: Option<Track<Color>>,
+    pub transforms: Vec<TransformTrack>,
+    pub materials: Vec<MaterialTrack>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // poses the camera and the world at `time`; fails if a track passes
+    // through a singular transform
+    pub fn apply(&self, time: f64, camera: &mut Camera, world: &mut World) -> Result<()> {
+        if let Some(track) = &self.camera {
+            camera.set_transform(track.at(time))?;
+        }
+        if let Some(track) = &self.light_position {
+            world.light.position = track.at(time);
+        }
+        if let Some(track) = &self.light_intensity {
+            world.light.intensity = track.at(time);
+        }
+        for track in &self.transforms {
+            world.shape_mut(track.shape).set_transform(track.at(time))?;
+        }
+        for track in &self.materials {
+            track.apply(time, world);
+        }
+        Ok(())
+    }
+}
+
+// A run of frames written as numbered images, `<prefix>0001.ppm` and so on,
+// into `directory`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Sequence {
+    pub frames: Range<u32>,
+    fps: f64,
+    pub directory: PathBuf,
+    pub prefix: String,
+}
+
+impl Sequence {
+    // fails unless `fps` is a positive, finite rate
+    pub fn new(frames: Range<u32>, fps: f64, directory: impl AsRef<Path>) -> Result<Self> {
+        if !(fps > 0.0 && fps.is_finite()) {
+            return Err(Error::InvalidFrameRate(fps));
+        }
+        Ok(Self {
+            frames,
+            fps,
+            directory: directory.as_ref().to_path_buf(),
+            prefix: String::from("frame_"),
+        })
+    }
+
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    pub fn time(&self, frame: u32) -> f64 {
+        frame as f64 / self.fps
+    }
+
+    pub fn frame_path(&self, frame: u32) -> PathBuf {
+        self.directory
+            .join(format!("{}{:04}.ppm", self.prefix, frame))
+    }
+
+    // poses and renders every frame with `render`, which can be a plain
+    // `Camera::render` or a path tracer, and returns the files written
+    pub fn render(
+        &self,
+        animation: &Animation,
+        camera: &mut Camera,
+        world: &mut World,
+        render: impl Fn(&Camera, &World) -> Canvas,
+    ) -> Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(&self.directory)?;
+        let mut written = Vec::new();
+        for frame in self.frames.clone() {
+            animation.apply(self.time(frame), camera, world)?;
+            let path = self.frame_path(frame);
+            render(camera, world).write_ppm(&path)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod animation_tests {
+    use std::f64::consts::PI;
+
+    use crate::features::{lights::Light, shape::Shape, spheres::Sphere, tuple::EPSILON};
+
+    use super::*;
+
+    #[test]
+    fn linear_track_interpolates_and_holds() {
+        let track = Track::linear(vec![(1.0, 10.0), (0.0, 0.0), (2.0, 0.0)]).unwrap();
+        assert_eq!(track.at(-1.0), 0.0);
+        assert_eq!(track.at(0.5), 5.0);
+        assert_eq!(track.at(1.5), 5.0);
+        assert_eq!(track.at(3.0), 0.0);
+    }
+
+    #[test]
+    fn spline_track_passes_through_keys_smoothly() {
+        let track = Track::spline(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 4.0), (3.0, 9.0)]).unwrap();
+        assert_eq!(track.at(1.0), 1.0);
+        assert_eq!(track.at(2.0), 4.0);
+        // the curve bends with its neighbours instead of following the chord
+        assert!((track.at(1.5) - 2.25).abs() < 0.2);
+        assert!(track.at(1.5) < 2.5);
+    }
+
+    #[test]
+    fn spline_track_follows_unevenly_spaced_keys() {
+        // keys on the line value = time, bunched up at both ends
+        let track =
+            Track::spline(vec![(0.0, 0.0), (1.0, 1.0), (10.0, 10.0), (11.0, 11.0)]).unwrap();
+        for time in [2.0, 5.5, 9.0] {
+            assert!((track.at(time) - time).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn tracks_hold_the_first_key_for_nan_times() {
+        let track = Track::spline(vec![(1.0, 3.0), (2.0, 5.0)]).unwrap();
+        assert_eq!(track.at(f64::NAN), 3.0);
+        assert_eq!(track.at(f64::NEG_INFINITY), 3.0);
+        assert_eq!(track.at(f64::INFINITY), 5.0);
+    }
+
+    #[test]
+    fn tuple_tracks_keep_their_kind() {
+        let track = Track::spline(vec![
+            (0.0, Point3::new(0.0, 0.0, 0.0)),
+            (1.0, Point3::new(2.0, 0.0, 0.0)),
+        ])
+        .unwrap();
+        assert_eq!(track.at(0.5), Point3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn tracks_need_keys_and_sequences_a_positive_rate() {
+        assert!(matches!(
+            Track::<f64>::linear(vec![]),
+            Err(Error::EmptyTrack)
+        ));
+        let directory = std::env::temp_dir();
+        assert!(matches!(
+            Sequence::new(0..1, 0.0, &directory),
+            Err(Error::InvalidFrameRate(_))
+        ));
+        assert!(Sequence::new(0..1, f64::NAN, &directory).is_err());
+        assert_eq!(Sequence::new(0..1, 24.0, &directory).unwrap().fps(), 24.0);
+    }
+
+    #[test]
+    fn transform_track_builds_trs_matrix() {
+        let mut world = World::default();
+        let mut track = TransformTrack::new(world.add(Shape::Sphere(Sphere::new())));
+        track.translation = Track::linear(vec![
+            (0.0, Vector3::new(0.0, 0.0, 0.0)),
+            (1.0, Vector3::new(0.0, 2.0, 0.0)),
+        ])
+        .unwrap();
+        track.rotation = Track::constant(Vector3::new(0.0, PI / 2.0, 0.0));
+        track.scale = Track::constant(Vector3::new(2.0, 2.0, 2.0));
+        let p = track.at(0.5) * Point3::new(0.0, 0.0, 1.0);
+        assert_eq!(p, Point3::new(2.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn animation_poses_camera_light_shapes_and_materials() {
+        let mut world = World::new(
+            Light::new(Point3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)),
+            &[],
+        );
+        let sphere = world.add(Shape::Sphere(Sphere::new()));
+        let mut camera = Camera::new(11.0, 11.0, PI / 2.0).unwrap();
+        let mut animation = Animation::new();
+        animation.camera = Some(CameraTrack::new(
+            Track::linear(vec![
+                (0.0, Point3::new(0.0, 0.0, -5.0)),
+                (2.0, Point3::new(0.0, 0.0, -9.0)),
+            ])
+            .unwrap(),
+            Track::constant(Point3::new(0.0, 0.0, 0.0)),
+            Track::constant(Vector3::new(0.0, 1.0, 0.0)),
+        ));
+        animation.light_position = Some(
+            Track::linear(vec![
+                (0.0, Point3::new(-10.0, 0.0, 0.0)),
+                (2.0, Point3::new(10.0, 0.0, 0.0)),
+            ])
+            .unwrap(),
+        );
+        let mut spin = TransformTrack::new(sphere);
+        spin.translation = Track::constant(Vector3::new(1.0, 0.0, 0.0));
+        animation.transforms.push(spin);
+        animation.materials.push(MaterialTrack::Ambient(
+            sphere,
+            Track::linear(vec![(0.0, 0.0), (2.0, 1.0)]).unwrap(),
+        ));
+
+        animation.apply(1.0, &mut camera, &mut world).unwrap();
+        assert_eq!(
+            camera.transform(),
+            &view_transformation(
+                Point3::new(0.0, 0.0, -7.0),
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            )
+        );
+        assert_eq!(world.light.position, Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(world.shape(sphere).transform(), &translation(1.0, 0.0, 0.0));
+        assert_eq!(world.shape(sphere).material().ambient, 0.5);
+    }
+
+    #[test]
+    fn sequence_writes_numbered_frames() {
+        let directory = std::env::temp_dir().join(format!("frames-{}", std::process::id()));
+        let sequence = Sequence::new(3..5, 24.0, &directory).unwrap();
+        assert_eq!(sequence.time(12), 0.5);
+        assert_eq!(sequence.frame_path(3), directory.join("frame_0003.ppm"));
+
+        let mut world = World::default();
+        let mut camera = Camera::new(4.0, 3.0, PI / 2.0).unwrap();
+        let written = sequence
+            .render(&Animation::new(), &mut camera, &mut world, |c, w| {
+                c.render(w)
+            })
+            .unwrap();
+        assert_eq!(
+            written,
+            vec![
+                directory.join("frame_0003.ppm"),
+                directory.join("frame_0004.ppm")
+            ]
+        );
+        let frame = Canvas::read_ppm(&written[1]).unwrap();
+        assert_eq!((frame.width, frame.height), (4, 3));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+use std::{f64::consts::PI, path::Path, sync::Arc};
+
+use super::{
+    canvas::Canvas,
+    error::Result,
+    sky::Sky,
+    tuple::{Color, Vector3},
+};
+
+// What a ray that leaves the scene sees, looked up by direction alone so the
+// environment sits infinitely far away. Used for camera rays and for
+// reflected and refracted rays alike.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Background {
+    Solid(Color),
+    // blends from `bottom` straight down to `top` straight up
+    Gradient { bottom: Color, top: Color },
+    // longitude across, latitude down, laid out the way an equirectangular
+    // camera at the origin renders the world
+    Equirectangular(Arc<Canvas>),
+    CubeMap(CubeMap),
+    // an analytic daylight sky
+    Sky(Sky),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Color::black())
+    }
+}
+
+impl Background {
+    pub fn equirectangular(image: Canvas) -> Self {
+        Background::Equirectangular(Arc::new(image))
+    }
+
+    // a `.hdr` or `.ppm` image
+    pub fn read_equirectangular(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::equirectangular(read_image(path.as_ref())?))
+    }
+
+    pub fn at(&self, direction: &Vector3) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let t = (direction.normalize().y + 1.0) / 2.0;
+                *bottom * (1.0 - t) + *top * t
+            }
+            Background::Equirectangular(image) => {
+                let (u, v) = equirectangular_uv(direction);
+                texel(image, u, v)
+            }
+            Background::CubeMap(cube) => cube.at(direction),
+            Background::Sky(sky) => sky.at(direction),
+        }
+    }
+}
+
+// Six square images, one per face of a cube around the scene, in the order
+// +x, -x, +y, -y, +z, -z and oriented as in OpenGL cube maps
+#[derive(Clone, PartialEq, Debug)]
+pub struct CubeMap {
+    pub faces: Arc<[Canvas; 6]>,
+}
+
+impl CubeMap {
+    pub fn new(faces: [Canvas; 6]) -> Self {
+        Self {
+            faces: Arc::new(faces),
+        }
+    }
+
+    // `.hdr` or `.ppm` images
+    pub fn read(paths: [impl AsRef<Path>; 6]) -> Result<Self> {
+        let mut faces = Vec::with_capacity(6);
+        for path in paths {
+            faces.push(read_image(path.as_ref())?);
+        }
+        let faces: [Canvas; 6] = faces.try_into().expect("six paths give six faces");
+        Ok(Self::new(faces))
+    }
+
+    pub fn at(&self, direction: &Vector3) -> Color {
+        let Vector3 { x, y, z } = *direction;
+        let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+        // the face the direction leaves through, and where on it in [-1, 1]
+        let (face, s, t, major) = if ax >= ay && ax >= az {
+            if x > 0.0 {
+                (0, -z, -y, ax)
+            } else {
+                (1, z, -y, ax)
+            }
+        } else if ay >= az {
+            if y > 0.0 {
+                (2, x, z, ay)
+            } else {
+                (3, x, -z, ay)
+            }
+        } else if z > 0.0 {
+            (4, x, -y, az)
+        } else {
+            (5, -x, -y, az)
+        };
+        texel(
+            &self.faces[face],
+            (s / major + 1.0) / 2.0,
+            (t / major + 1.0) / 2.0,
+        )
+    }
+}
+
+// where `direction` lands on an equirectangular image, in [0, 1] with v
+// running down the image
+pub fn equirectangular_uv(direction: &Vector3) -> (f64, f64) {
+    let d = direction.normalize();
+    let longitude = d.x.atan2(-d.z);
+    let latitude = d.y.clamp(-1.0, 1.0).asin();
+    ((1.0 - longitude / PI) / 2.0, 0.5 - latitude / PI)
+}
+
+pub fn equirectangular_direction(u: f64, v: f64) -> Vector3 {
+    let longitude = PI * (1.0 - 2.0 * u);
+    let latitude = PI * (0.5 - v);
+    Vector3::new(
+        longitude.sin() * latitude.cos(),
+        latitude.sin(),
+        -longitude.cos() * latitude.cos(),
+    )
+}
+
+pub(crate) fn read_image(path: &Path) -> Result<Canvas> {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("hdr"))
+    {
+        Canvas::read_hdr(path)
+    } else {
+        Canvas::read_ppm(path)
+    }
+}
+
+// the pixel under (u, v) in [0, 1], with v running down the image
+pub(crate) fn texel(image: &Canvas, u: f64, v: f64) -> Color {
+    let x = ((u * image.width as f64) as usize).min(image.width - 1);
+    let y = ((v * image.height as f64) as usize).min(image.height - 1);
+    image.canvas[y][x]
+}
+
+#[cfg(test)]
+mod background_tests {
+    use std::f64::consts::PI;
+
+    use crate::features::camera::cameras::Camera;
+
+    use super::*;
+
+    #[test]
+    fn solid_and_gradient_backgrounds() {
+        let solid = Background::Solid(Color::new(0.2, 0.3, 0.4));
+        assert_eq!(
+            solid.at(&Vector3::new(1.0, 2.0, 3.0)),
+            Color::new(0.2, 0.3, 0.4)
+        );
+        let sky = Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.0, 0.0, 1.0),
+        };
+        assert_eq!(
+            sky.at(&Vector3::new(0.0, 3.0, 0.0)),
+            Color::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            sky.at(&Vector3::new(0.0, 0.0, -1.0)),
+            Color::new(0.5, 0.5, 1.0)
+        );
+        assert_eq!(
+            Background::default().at(&Vector3::new(0.0, 1.0, 0.0)),
+            Color::black()
+        );
+    }
+
+    #[test]
+    fn equirectangular_matches_the_equirectangular_camera() {
+        let mut image = Canvas::new(8, 4);
+        for y in 0..4 {
+            for x in 0..8 {
+                image.canvas[y][x] = Color::new(x as f64, y as f64, 0.0);
+            }
+        }
+        let background = Background::equirectangular(image);
+        let camera = Camera::equirectangular(8.0, 4.0).unwrap();
+        for y in 0..4 {
+            for x in 0..8 {
+                let r = camera.ray_for_pixel(x as f64, y as f64);
+                assert_eq!(
+                    background.at(&r.direction),
+                    Color::new(x as f64, y as f64, 0.0)
+                );
+            }
+        }
+        let forward = background.at(&Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!(forward, Color::new(4.0, 2.0, 0.0));
+        let up = background.at(&Vector3::new((PI / 8.0).sin(), 1.0, 0.0));
+        assert_eq!(up.g, 0.0);
+    }
+
+    #[test]
+    fn equirectangular_mapping_round_trips() {
+        for (u, v) in [(0.5, 0.5), (0.1, 0.3), (0.9, 0.8), (0.25, 0.05)] {
+            let d = equirectangular_direction(u, v);
+            let (u2, v2) = equirectangular_uv(&d);
+            assert!((u - u2).abs() < 1e-9 && (v - v2).abs() < 1e-9);
+        }
+        assert_eq!(
+            equirectangular_direction(0.5, 0.5),
+            Vector3::new(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn cube_map_picks_the_face_a_direction_leaves_through() {
+        let faces = std::array::from_fn(|ix| {
+            let mut face = Canvas::new(2, 2);
+            for y in 0..2 {
+                for x in 0..2 {
+                    face.canvas[y][x] = Color::new(ix as f64, x as f64, y as f64);
+                }
+            }
+            face
+        });
+        let cube = Background::CubeMap(CubeMap::new(faces));
+        let looks = [
+            (Vector3::new(1.0, 0.2, 0.1), 0.0),
+            (Vector3::new(-1.0, 0.2, 0.1), 1.0),
+            (Vector3::new(0.1, 1.0, 0.2), 2.0),
+            (Vector3::new(0.1, -1.0, 0.2), 3.0),
+            (Vector3::new(0.1, 0.2, 1.0), 4.0),
+            (Vector3::new(0.1, 0.2, -1.0), 5.0),
+        ];
+        for (direction, face) in looks {
+            assert_eq!(cube.at(&direction).r, face);
+        }
+        // +z face: +x to the right, +y up
+        assert_eq!(
+            cube.at(&Vector3::new(0.5, 0.5, 1.0)),
+            Color::new(4.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            cube.at(&Vector3::new(-0.5, -0.5, 1.0)),
+            Color::new(4.0, 0.0, 1.0)
+        );
+    }
+}
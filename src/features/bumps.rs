@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use super::{
+    canvas::Canvas,
+    noise::perlin,
+    shape::Shape,
+    tuple::{Color, Point3, Vector3},
+};
+
+// step used for the central differences of procedural height fields
+const DELTA: f64 = 0.001;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Bump {
+    Noise(NoiseBump),
+    Ripple(Ripple),
+    Map(NormalMap),
+}
+
+impl Bump {
+    // `normal` is the geometric world normal at the world `point`
+    pub fn perturb(&self, shape: &Shape, point: &Point3, normal: &Vector3) -> Vector3 {
+        let object_point = shape.inverse() * *point;
+        match self {
+            Bump::Noise(noise) => {
+                perturb_by_height(shape, &object_point, normal, |p| noise.height(p))
+            }
+            Bump::Ripple(ripple) => {
+                perturb_by_height(shape, &object_point, normal, |p| ripple.height(p))
+            }
+            Bump::Map(map) => map.perturb(shape, &object_point, normal),
+        }
+    }
+}
+
+// tilts the normal against the slope of a height field given in object space
+fn perturb_by_height(
+    shape: &Shape,
+    object_point: &Point3,
+    normal: &Vector3,
+    height: impl Fn(&Point3) -> f64,
+) -> Vector3 {
+    let dx = Vector3::new(DELTA, 0.0, 0.0);
+    let dy = Vector3::new(0.0, DELTA, 0.0);
+    let dz = Vector3::new(0.0, 0.0, DELTA);
+    let object_gradient = Vector3::new(
+        height(&(*object_point + dx)) - height(&(*object_point - dx)),
+        height(&(*object_point + dy)) - height(&(*object_point - dy)),
+        height(&(*object_point + dz)) - height(&(*object_point - dz)),
+    ) / (2.0 * DELTA);
+    let gradient = shape.inverse_transpose() * object_gradient;
+    let tangential = gradient - *normal * gradient.dot(normal);
+    (*normal - tangential).normalize()
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct NoiseBump {
+    pub scale: f64,
+    pub amplitude: f64,
+}
+
+impl NoiseBump {
+    pub fn new(scale: f64, amplitude: f64) -> Self {
+        Self { scale, amplitude }
+    }
+    pub fn height(&self, point: &Point3) -> f64 {
+        self.amplitude * perlin(&(Point3::ORIGIN + (*point - Point3::ORIGIN) * self.scale))
+    }
+}
+
+// concentric sine waves around the object's y axis
+#[derive(PartialEq, Debug, Clone)]
+pub struct Ripple {
+    pub frequency: f64,
+    pub amplitude: f64,
+}
+
+impl Ripple {
+    pub fn new(frequency: f64, amplitude: f64) -> Self {
+        Self {
+            frequency,
+            amplitude,
+        }
+    }
+    pub fn height(&self, point: &Point3) -> f64 {
+        let distance = (point.x * point.x + point.z * point.z).sqrt();
+        self.amplitude * (self.frequency * distance).sin()
+    }
+}
+
+// tangent-space normal map: red is along the tangent, green along the
+// bitangent and blue along the surface normal, each mapped from [0, 1] to [-1, 1]
+#[derive(PartialEq, Debug, Clone)]
+pub struct NormalMap {
+    pub image: Arc<Canvas>,
+}
+
+impl NormalMap {
+    pub fn new(image: Canvas) -> Self {
+        Self {
+            image: Arc::new(image),
+        }
+    }
+
+    pub fn perturb(&self, shape: &Shape, object_point: &Point3, normal: &Vector3) -> Vector3 {
+        // normals go to world space through the inverse transpose, so the
+        // transpose of the transform brings them back
+        let object_normal = (shape.transform().transpose() * *normal).normalize();
+        let (tangent, bitangent) = tangent_frame(&object_normal);
+        let (u, v) = uv_at(shape, object_point);
+        let texel = self.sample(u, v);
+        let mapped = tangent * (texel.r * 2.0 - 1.0)
+            + bitangent * (texel.g * 2.0 - 1.0)
+            + object_normal * (texel.b * 2.0 - 1.0);
+        (shape.inverse_transpose() * mapped).normalize()
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Color {
+        let u = u - u.floor();
+        let v = v - v.floor();
+        let x = (u * (self.image.width - 1) as f64).round() as usize;
+        let y = ((1.0 - v) * (self.image.height - 1) as f64).round() as usize;
+        self.image.canvas[y][x]
+    }
+}
+
+fn tangent_frame(normal: &Vector3) -> (Vector3, Vector3) {
+    let mut up = Vector3::new(0.0, 1.0, 0.0);
+    if normal.dot(&up).abs() > 0.999 {
+        up = Vector3::new(0.0, 0.0, 1.0);
+    }
+    let tangent = normal.cross(&up).normalize();
+    let bitangent = tangent.cross(normal);
+    (tangent, bitangent)
+}
+
+// texture coordinates in [0, 1) for a point given in object space
+pub fn uv_at(shape: &Shape, point: &Point3) -> (f64, f64) {
+    shape.primitive().local_uv(*point)
+}
+
+#[cfg(test)]
+mod bump_tests {
+    use crate::features::{
+        planes::Plane, shape::Primitive, spheres::Sphere, transformations::scaling,
+    };
+
+    use super::*;
+
+    #[test]
+    fn flat_height_field_keeps_normal() {
+        let shape = Shape::Plane(Plane::new());
+        let bump = Bump::Noise(NoiseBump::new(4.0, 0.0));
+        let normal = Vector3::new(0.0, 1.0, 0.0);
+        let n = bump.perturb(&shape, &Point3::new(0.3, 0.0, 0.7), &normal);
+        assert_eq!(n, normal);
+    }
+
+    #[test]
+    fn noise_bump_tilts_normal() {
+        let shape = Shape::Plane(Plane::new());
+        let bump = Bump::Noise(NoiseBump::new(4.0, 0.5));
+        let normal = Vector3::new(0.0, 1.0, 0.0);
+        let n = bump.perturb(&shape, &Point3::new(0.3, 0.0, 0.7), &normal);
+        assert!(n != normal);
+        assert!((n.magnitude() - 1.0).abs() < 0.0001);
+        assert!(n.y > 0.0);
+    }
+
+    #[test]
+    fn ripple_follows_slope_of_wave() {
+        let shape = Shape::Plane(Plane::new());
+        let bump = Bump::Ripple(Ripple::new(2.0, 0.1));
+        let normal = Vector3::new(0.0, 1.0, 0.0);
+        let n = bump.perturb(&shape, &Point3::new(0.5, 0.0, 0.0), &normal);
+        let slope = 0.1 * 2.0 * (2.0_f64 * 0.5).cos();
+        let expected = Vector3::new(-slope, 1.0, 0.0).normalize();
+        assert!((n.x - expected.x).abs() < 0.0001);
+        assert!((n.y - expected.y).abs() < 0.0001);
+        assert!(n.z.abs() < 0.0001);
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_normal() {
+        let mut image = Canvas::new(2, 2);
+        for y in 0..2 {
+            for x in 0..2 {
+                image.write_pixel(x, y, Color::new(0.5, 0.5, 1.0)).unwrap();
+            }
+        }
+        let mut sphere = Sphere::new();
+        sphere.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let shape = Shape::Sphere(sphere);
+        let bump = Bump::Map(NormalMap::new(image));
+        let point = Point3::new(0.0, 0.0, -2.0);
+        let normal = shape.normal_at(point);
+        assert_eq!(bump.perturb(&shape, &point, &normal), normal);
+    }
+
+    #[test]
+    fn normal_map_bends_towards_tangent() {
+        let mut image = Canvas::new(1, 1);
+        image.write_pixel(0, 0, Color::new(1.0, 0.5, 0.5)).unwrap();
+        let shape = Shape::Sphere(Sphere::new());
+        let bump = Bump::Map(NormalMap::new(image));
+        let point = Point3::new(0.0, 0.0, -1.0);
+        let n = bump.perturb(&shape, &point, &shape.normal_at(point));
+        assert_eq!(n, Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn spherical_uv_mapping() {
+        let shape = Shape::Sphere(Sphere::new());
+        let cases = [
+            (Point3::new(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Point3::new(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Point3::new(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Point3::new(0.0, 1.0, 0.0), (0.5, 1.0)),
+        ];
+        for (point, (u, v)) in cases {
+            let (mu, mv) = uv_at(&shape, &point);
+            assert!((mu - u).abs() < 0.0001);
+            assert!((mv - v).abs() < 0.0001);
+        }
+    }
+}
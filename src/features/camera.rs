@@ -1,32 +1,163 @@
 pub mod cameras {
+    use std::f64::consts::PI;
+
     use indicatif::ProgressBar;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
     use crate::features::{
-        canvas::Canvas, matrice::Matrice, rays::Ray, tuple::Tuple, world::World,
+        canvas::Canvas,
+        error::{Error, Result},
+        matrice::Matrice4,
+        packets::{hit_world_packet, RayPacket},
+        rays::Ray,
+        sampling::{splitmix64, uniform_disk},
+        transformations::Transform,
+        tuple::{Color, Point3, Vector3},
+        world::World,
     };
 
+    fn is_positive(x: f64) -> bool {
+        x > 0.0 && x.is_finite()
+    }
+
+    // camera rays traced together by `render` when there is one ray per pixel
+    const PACKET_WIDTH: usize = 8;
+
+    // shape of the lens opening, which is also the shape of out-of-focus highlights
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum Bokeh {
+        Disk,
+        Polygon(u32),
+    }
+
+    impl Bokeh {
+        // a point spread uniformly over the opening, which fits in the unit disk
+        pub fn sample(&self, u1: f64, u2: f64) -> (f64, f64) {
+            match *self {
+                Bokeh::Disk => uniform_disk(u1, u2),
+                Bokeh::Polygon(blades) => {
+                    let blades = blades.max(3);
+                    let sector = ((u1 * blades as f64) as u32).min(blades - 1);
+                    let u1 = u1 * blades as f64 - sector as f64;
+                    let a0 = 2.0 * PI * sector as f64 / blades as f64;
+                    let a1 = 2.0 * PI * (sector + 1) as f64 / blades as f64;
+                    // uniform point in the triangle between the center and one edge
+                    let s = u1.sqrt();
+                    let (b0, b1) = (s * (1.0 - u2), s * u2);
+                    (b0 * a0.cos() + b1 * a1.cos(), b0 * a0.sin() + b1 * a1.sin())
+                }
+            }
+        }
+    }
+
+    // how directions around the camera are laid out on the image
+    #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+    pub enum Projection {
+        #[default]
+        Perspective,
+        // parallel rays leaving a plane `2 * half_width` wide
+        Orthographic,
+        // equidistant fisheye: the angle off the view axis grows linearly with
+        // the distance from the image center, `field_of_view` across the wider side
+        Fisheye,
+        // latitude and longitude over the whole sphere, for 2:1 panoramas
+        Equirectangular,
+    }
+
+    #[derive(Clone, Debug)]
     pub struct Camera {
-        pub hsize: f32,
-        pub vsize: f32,
-        pub field_of_view: f32,
-        pub transform: Matrice,
-        pub pixel_size: f32,
-        pub half_width: f32,
-        pub half_height: f32,
+        pub hsize: f64,
+        pub vsize: f64,
+        pub field_of_view: f64,
+        pub projection: Projection,
+        transform: Transform,
+        pub pixel_size: f64,
+        pub half_width: f64,
+        pub half_height: f64,
+        // lens radius in world units; zero makes a pinhole with everything in focus
+        pub aperture: f64,
+        // distance from the camera to the plane that is in perfect focus
+        pub focal_distance: f64,
+        pub bokeh: Bokeh,
+        // rays traced per pixel by `render`
+        pub samples: usize,
+        // rays sample times between the shutter opening and closing
+        pub shutter_open: f64,
+        pub shutter_close: f64,
+        pub seed: u64,
     }
 
     impl Camera {
-        pub fn new(hsize: f32, vsize: f32, field_of_view: f32) -> Camera {
+        // fails unless the image has a positive size and the field of view
+        // is a positive angle narrower than a half turn
+        pub fn new(hsize: f64, vsize: f64, field_of_view: f64) -> Result<Camera> {
+            if field_of_view >= PI {
+                return Err(Error::InvalidFieldOfView(field_of_view));
+            }
+            let half_view = (field_of_view / 2.0).tan();
+            Self::framed(
+                hsize,
+                vsize,
+                field_of_view,
+                half_view,
+                Projection::Perspective,
+            )
+        }
+
+        // looks straight down -z through a window `view_width` wide
+        pub fn orthographic(hsize: f64, vsize: f64, view_width: f64) -> Result<Camera> {
+            if !is_positive(view_width) {
+                return Err(Error::InvalidFieldOfView(view_width));
+            }
+            Self::framed(
+                hsize,
+                vsize,
+                0.0,
+                view_width / 2.0,
+                Projection::Orthographic,
+            )
+        }
+
+        // `field_of_view` can go up to 2π, which sees all around
+        pub fn fisheye(hsize: f64, vsize: f64, field_of_view: f64) -> Result<Camera> {
+            Self::framed(hsize, vsize, field_of_view, 1.0, Projection::Fisheye)
+        }
+
+        pub fn equirectangular(hsize: f64, vsize: f64) -> Result<Camera> {
+            Self::framed(hsize, vsize, 2.0 * PI, 1.0, Projection::Equirectangular)
+        }
+
+        // `half_view` is half the extent of the wider side of the image plane
+        fn framed(
+            hsize: f64,
+            vsize: f64,
+            field_of_view: f64,
+            half_view: f64,
+            projection: Projection,
+        ) -> Result<Camera> {
+            if !is_positive(hsize) || !is_positive(vsize) {
+                return Err(Error::InvalidImageSize { hsize, vsize });
+            }
+            if projection != Projection::Orthographic && !is_positive(field_of_view) {
+                return Err(Error::InvalidFieldOfView(field_of_view));
+            }
             let mut out = Self {
                 hsize,
                 vsize,
                 field_of_view,
-                transform: Matrice::identity_matrix(4),
+                projection,
+                transform: Transform::default(),
                 pixel_size: 0.0,
                 half_height: 0.0,
                 half_width: 0.0,
+                aperture: 0.0,
+                focal_distance: 1.0,
+                bokeh: Bokeh::Disk,
+                samples: 1,
+                shutter_open: 0.0,
+                shutter_close: 0.0,
+                seed: 0,
             };
-            let half_view = (field_of_view / 2.0).tan();
             let aspect = hsize / vsize;
             if aspect >= 1.0 {
                 out.half_width = half_view;
@@ -36,9 +167,19 @@ pub mod cameras {
                 out.half_height = half_view;
             }
             out.pixel_size = (out.half_width * 2.0) / out.hsize;
-            out
+            Ok(out)
+        }
+        pub fn ray_for_pixel(&self, px: f64, py: f64) -> Ray {
+            let (origin, direction) = self.camera_ray(px, py);
+            let inverse = self.transform.inverse();
+            let origin = inverse * origin;
+            let direction = (inverse * direction).normalize();
+            Ray::with_time(origin, direction, self.shutter_open)
         }
-        pub fn ray_for_pixel(&self, px: f32, py: f32) -> Ray {
+
+        // origin and direction of the ray for the pixel before the camera
+        // transform is applied
+        fn camera_ray(&self, px: f64, py: f64) -> (Point3, Vector3) {
             // the offset from the edge of the canvas to the pixel's center
             let xoffset = (px + 0.5) * self.pixel_size;
             let yoffset = (py + 0.5) * self.pixel_size;
@@ -48,33 +189,164 @@ pub mod cameras {
             let world_x = self.half_width - xoffset;
             let world_y = self.half_height - yoffset;
 
-            //  using the camera matrix, transform the canvas point and the origin,
-            //  and then compute the ray's direction vector.
-            //  (remember that the canvas is at z=-1)
+            let eye = Point3::new(0.0, 0.0, 0.0);
+            match self.projection {
+                //  the canvas is at z=-1
+                Projection::Perspective => {
+                    let pixel = Point3::new(world_x, world_y, -1.0);
+                    (eye, (pixel - eye).normalize())
+                }
+                Projection::Orthographic => (
+                    Point3::new(world_x, world_y, 0.0),
+                    Vector3::new(0.0, 0.0, -1.0),
+                ),
+                Projection::Fisheye => {
+                    // world_x and world_y run over [-1, 1] across the wider side
+                    let theta =
+                        (world_x * world_x + world_y * world_y).sqrt() * self.field_of_view / 2.0;
+                    let phi = world_y.atan2(world_x);
+                    let direction = Vector3::new(
+                        theta.sin() * phi.cos(),
+                        theta.sin() * phi.sin(),
+                        -theta.cos(),
+                    );
+                    (eye, direction)
+                }
+                Projection::Equirectangular => {
+                    let longitude = PI * (1.0 - 2.0 * (px + 0.5) / self.hsize);
+                    let latitude = PI * (0.5 - (py + 0.5) / self.vsize);
+                    let direction = Vector3::new(
+                        longitude.sin() * latitude.cos(),
+                        latitude.sin(),
+                        -longitude.cos() * latitude.cos(),
+                    );
+                    (eye, direction)
+                }
+            }
+        }
+
+        // the view transformation, usually built with `view_transformation`
+        pub fn set_transform(&mut self, transform: Matrice4) -> Result<()> {
+            self.transform = Transform::new(transform)?;
+            Ok(())
+        }
+        pub fn transform(&self) -> &Matrice4 {
+            self.transform.matrix()
+        }
+
+        // maps a uniform number in [0, 1) to a moment the shutter is open
+        pub fn shutter_time(&self, u: f64) -> f64 {
+            self.shutter_open + (self.shutter_close - self.shutter_open) * u
+        }
+
+        // thin lens model: the ray leaves the lens at (lens_x, lens_y), given
+        // within the unit disk, and passes through the point in focus that the
+        // pinhole ray for the pixel would hit. Flat projections focus on the
+        // plane `focal_distance` ahead, the wide-angle ones on a sphere.
+        pub fn lens_ray_for_pixel(&self, px: f64, py: f64, lens_x: f64, lens_y: f64) -> Ray {
+            let (center, direction) = self.camera_ray(px, py);
+            let distance = match self.projection {
+                Projection::Perspective | Projection::Orthographic => {
+                    self.focal_distance / -direction.z
+                }
+                Projection::Fisheye | Projection::Equirectangular => self.focal_distance,
+            };
+            let focus = center + direction * distance;
+            let lens = center + Vector3::new(lens_x * self.aperture, lens_y * self.aperture, 0.0);
+            let inverse = self.transform.inverse();
+            let origin = inverse * lens;
+            let direction = (inverse * focus - origin).normalize();
+            Ray::with_time(origin, direction, self.shutter_open)
+        }
+
+        // one random ray for the pixel: jittered inside it when taking several
+        // samples, then spread over the lens and shutter by `lens_ray`
+        pub fn sample_ray(&self, px: f64, py: f64, rng: &mut impl Rng) -> Ray {
+            let (mut px, mut py) = (px, py);
+            if self.samples > 1 {
+                px += rng.gen::<f64>() - 0.5;
+                py += rng.gen::<f64>() - 0.5;
+            }
+            self.lens_ray(px, py, rng)
+        }
+
+        // a random ray through the image point (px, py): spread over the lens
+        // opening when the aperture is open and sent at a random moment while
+        // the shutter is open
+        pub fn lens_ray(&self, px: f64, py: f64, rng: &mut impl Rng) -> Ray {
+            let mut ray = if self.aperture == 0.0 {
+                self.ray_for_pixel(px, py)
+            } else {
+                let (lens_x, lens_y) = self.bokeh.sample(rng.gen(), rng.gen());
+                self.lens_ray_for_pixel(px, py, lens_x, lens_y)
+            };
+            if self.shutter_close > self.shutter_open {
+                ray.time = self.shutter_time(rng.gen());
+            }
+            ray
+        }
 
-            let pixel = self.transform.inverse().unwrap() * Tuple::point(world_x, world_y, -1.0);
-            let origin = self.transform.inverse().unwrap() * Tuple::point(0.0, 0.0, 0.0);
-            let direction = (pixel - origin).normalize();
-            Ray::new(origin, direction)
+        // every pixel gets its own generator so renders are reproducible; the
+        // seed and pixel are hashed together so neighbouring seeds do not give
+        // the same streams shifted by a pixel
+        pub fn pixel_rng(&self, x: usize, y: usize) -> StdRng {
+            let index = (y * self.hsize as usize + x) as u64;
+            StdRng::seed_from_u64(splitmix64(splitmix64(self.seed) ^ index))
         }
+
         pub fn render(&self, world: &World) -> Canvas {
             let mut image = Canvas::new(self.hsize as usize, self.vsize as usize);
             let bar = ProgressBar::new(self.vsize as u64);
-            for y in 0..self.vsize as usize - 1 {
-                for x in 0..self.hsize as usize - 1 {
-                    let ray = self.ray_for_pixel(x as f32, y as f32);
-                    let color = world.color_at(&ray);
-                    image.write_pixel(x, y, color);
+            for (y, row) in image.canvas.iter_mut().enumerate() {
+                // packets share the shutter_open instant, so they only serve
+                // single-sample frames without depth of field or motion blur
+                if self.samples <= 1
+                    && self.aperture == 0.0
+                    && self.shutter_close <= self.shutter_open
+                {
+                    self.render_row_packets(world, y, row);
+                } else {
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        let mut rng = self.pixel_rng(x, y);
+                        let mut sum = Color::black();
+                        for _ in 0..self.samples.max(1) {
+                            let ray = self.sample_ray(x as f64, y as f64, &mut rng);
+                            sum = sum + world.color_at(&ray, world.max_depth);
+                        }
+                        *pixel = sum / self.samples.max(1) as f64;
+                    }
                 }
                 bar.inc(1);
             }
             image
         }
+
+        // one pinhole ray per pixel, traced to the first hit PACKET_WIDTH
+        // neighbours at a time; what is left at the end of the row goes alone
+        fn render_row_packets(&self, world: &World, y: usize, row: &mut [Color]) {
+            let packed = row.len() - row.len() % PACKET_WIDTH;
+            let mut chunks = row.chunks_exact_mut(PACKET_WIDTH);
+            for (ix, chunk) in chunks.by_ref().enumerate() {
+                let x = ix * PACKET_WIDTH;
+                let rays: [Ray; PACKET_WIDTH] =
+                    std::array::from_fn(|i| self.ray_for_pixel((x + i) as f64, y as f64));
+                let hits = hit_world_packet(world, &RayPacket::from_rays(&rays));
+                for ((pixel, ray), hit) in chunk.iter_mut().zip(&rays).zip(hits) {
+                    *pixel = world.color_for_hit(ray, hit, world.max_depth);
+                }
+            }
+            for (i, pixel) in chunks.into_remainder().iter_mut().enumerate() {
+                let x = packed + i;
+                *pixel = world.color_at(&self.ray_for_pixel(x as f64, y as f64), world.max_depth);
+            }
+        }
     }
 
     #[cfg(test)]
     mod cameras_tests {
-        use std::f32::consts::PI;
+        use std::f64::consts::PI;
+
+        use crate::features::tuple::EPSILON;
 
         use super::*;
         #[test]
@@ -82,77 +354,373 @@ pub mod cameras {
             let hsize = 160.0;
             let vsize = 120.0;
             let field_of_view = PI / 2.0;
-            let c = Camera::new(hsize, vsize, field_of_view);
+            let c = Camera::new(hsize, vsize, field_of_view).unwrap();
             assert_eq!(c.hsize, hsize);
             assert_eq!(c.vsize, vsize);
             assert_eq!(c.field_of_view, field_of_view);
-            assert_eq!(c.transform, Matrice::identity_matrix(4));
+            assert_eq!(c.transform(), &Matrice4::identity());
+        }
+        #[test]
+        fn degenerate_cameras_are_rejected() {
+            for (hsize, vsize) in [(0.0, 10.0), (10.0, -1.0), (f64::NAN, 10.0)] {
+                assert!(matches!(
+                    Camera::new(hsize, vsize, PI / 2.0),
+                    Err(Error::InvalidImageSize { .. })
+                ));
+            }
+            for fov in [0.0, -1.0, PI, f64::NAN, f64::INFINITY] {
+                assert!(matches!(
+                    Camera::new(10.0, 10.0, fov),
+                    Err(Error::InvalidFieldOfView(_))
+                ));
+            }
+            assert!(Camera::orthographic(10.0, 10.0, 0.0).is_err());
+            assert!(Camera::fisheye(10.0, 10.0, f64::NAN).is_err());
+            assert!(Camera::equirectangular(0.0, 10.0).is_err());
+            assert!(Camera::fisheye(10.0, 10.0, 2.0 * PI).is_ok());
         }
+
         #[test]
         fn testing_pixel_size_horizontal_canvas() {
-            let c = Camera::new(200.0, 125.0, PI / 2.0);
-            assert_eq!(c.pixel_size, 0.01);
+            let c = Camera::new(200.0, 125.0, PI / 2.0).unwrap();
+            assert!((c.pixel_size - 0.01).abs() < EPSILON);
         }
 
         #[test]
         fn testing_pixel_size_vertical_canvas() {
-            let c = Camera::new(125.0, 200.0, PI / 2.0);
-            assert_eq!(c.pixel_size, 0.01);
+            let c = Camera::new(125.0, 200.0, PI / 2.0).unwrap();
+            assert!((c.pixel_size - 0.01).abs() < EPSILON);
         }
     }
 
     #[cfg(test)]
     mod ray_for_pixel_tests {
-        use std::f32::consts::PI;
+        use std::f64::consts::PI;
 
         use crate::features::transformations::{rotation_y, translation};
 
         use super::*;
         #[test]
         fn test_ray_through_canvas_center() {
-            let c = Camera::new(201.0, 101.0, PI / 2.0);
+            let c = Camera::new(201.0, 101.0, PI / 2.0).unwrap();
             let r = c.ray_for_pixel(100.0, 50.0);
-            assert_eq!(r.origin, Tuple::point(0.0, 0.0, 0.0));
-            assert_eq!(r.direction, Tuple::vector(0.0, 0.0, -1.0));
+            assert_eq!(r.origin, Point3::new(0.0, 0.0, 0.0));
+            assert_eq!(r.direction, Vector3::new(0.0, 0.0, -1.0));
         }
 
         #[test]
         fn test_ray_through_canvas_corner() {
-            let c = Camera::new(201.0, 101.0, PI / 2.0);
+            let c = Camera::new(201.0, 101.0, PI / 2.0).unwrap();
             let r = c.ray_for_pixel(0.0, 0.0);
-            assert_eq!(r.origin, Tuple::point(0.0, 0.0, 0.0));
-            assert_eq!(r.direction, Tuple::vector(0.66519, 0.33259, -0.66851));
+            assert_eq!(r.origin, Point3::new(0.0, 0.0, 0.0));
+            assert_eq!(r.direction, Vector3::new(0.66519, 0.33259, -0.66851));
         }
 
         #[test]
         fn test_ray_camera_transformed() {
-            let mut c = Camera::new(201.0, 101.0, PI / 2.0);
-            c.transform = rotation_y(PI / 4.0) * translation(0.0, -2.0, 5.0);
+            let mut c = Camera::new(201.0, 101.0, PI / 2.0).unwrap();
+            c.set_transform(rotation_y(PI / 4.0) * translation(0.0, -2.0, 5.0))
+                .unwrap();
             let r = c.ray_for_pixel(100.0, 50.0);
-            assert_eq!(r.origin, Tuple::point(0.0, 2.0, -5.0));
+            assert_eq!(r.origin, Point3::new(0.0, 2.0, -5.0));
             assert_eq!(
                 r.direction,
-                Tuple::vector(2.0_f32.sqrt() / 2.0, 0.0, -(2.0_f32.sqrt() / 2.0))
+                Vector3::new(2.0_f64.sqrt() / 2.0, 0.0, -(2.0_f64.sqrt() / 2.0))
             );
         }
     }
     #[cfg(test)]
     mod rendering_tests {
-        use std::f32::consts::PI;
+        use std::f64::consts::PI;
 
-        use crate::features::transformations::view_transformation;
+        use crate::features::{background::Background, transformations::view_transformation};
 
         use super::*;
         #[test]
         fn test_rendering_world_with_camera() {
             let world = World::default();
-            let mut c = Camera::new(11.0, 11.0, PI / 2.0);
-            let from = Tuple::point(0.0, 0.0, -5.0);
-            let to = Tuple::point(0.0, 0.0, 0.0);
-            let up = Tuple::vector(0.0, 1.0, 0.0);
-            c.transform = view_transformation(from, to, up);
+            let mut c = Camera::new(11.0, 11.0, PI / 2.0).unwrap();
+            let from = Point3::new(0.0, 0.0, -5.0);
+            let to = Point3::new(0.0, 0.0, 0.0);
+            let up = Vector3::new(0.0, 1.0, 0.0);
+            c.set_transform(view_transformation(from, to, up)).unwrap();
+            let image = c.render(&world);
+            assert_eq!(
+                image.pixel_at(5, 5).unwrap(),
+                Color::new(0.38066, 0.47583, 0.2855)
+            )
+        }
+
+        #[test]
+        fn packet_rendering_matches_ray_by_ray() {
+            let world = World::default();
+            // not a multiple of the packet width, so rows end with single rays
+            let mut c = Camera::new(21.0, 11.0, PI / 2.0).unwrap();
+            c.set_transform(view_transformation(
+                Point3::new(0.0, 0.0, -5.0),
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+            let image = c.render(&world);
+            for y in 0..11 {
+                for x in 0..21 {
+                    let expected =
+                        world.color_at(&c.ray_for_pixel(x as f64, y as f64), world.max_depth);
+                    assert_eq!(image.pixel_at(x, y).unwrap(), expected);
+                }
+            }
+        }
+
+        #[test]
+        fn rendering_fills_the_last_row() {
+            let mut world = World::new(World::default().light, &[]);
+            world.background = Background::Solid(Color::new(0.25, 0.5, 0.75));
+            let c = Camera::new(4.0, 3.0, PI / 2.0).unwrap();
             let image = c.render(&world);
-            assert_eq!(image.pixel_at(5, 5), Tuple::color(0.38066, 0.47583, 0.2855))
+            for x in 0..4 {
+                assert_eq!(image.pixel_at(x, 2).unwrap(), Color::new(0.25, 0.5, 0.75));
+            }
+        }
+
+        #[test]
+        fn adjacent_seeds_are_not_shifted_copies() {
+            let c = Camera::new(4.0, 3.0, PI / 2.0).unwrap();
+            let mut next = Camera::new(4.0, 3.0, PI / 2.0).unwrap();
+            next.seed = 1;
+            let a: u64 = c.pixel_rng(1, 0).gen();
+            let b: u64 = next.pixel_rng(0, 0).gen();
+            assert_ne!(a, b);
+            let again: u64 = c.pixel_rng(1, 0).gen();
+            assert_eq!(a, again);
+        }
+    }
+
+    #[cfg(test)]
+    mod depth_of_field_tests {
+        use std::f64::consts::PI;
+
+        use crate::features::{
+            shape::Shape,
+            spheres::Sphere,
+            transformations::{translation, view_transformation},
+        };
+
+        use super::*;
+
+        #[test]
+        fn centered_lens_ray_matches_pinhole() {
+            let mut c = Camera::new(201.0, 101.0, PI / 2.0).unwrap();
+            c.aperture = 0.5;
+            c.focal_distance = 4.0;
+            c.set_transform(translation(0.0, -2.0, 5.0)).unwrap();
+            let pinhole = c.ray_for_pixel(10.0, 20.0);
+            let lens = c.lens_ray_for_pixel(10.0, 20.0, 0.0, 0.0);
+            assert_eq!(lens.origin, pinhole.origin);
+            assert_eq!(lens.direction, pinhole.direction);
+        }
+
+        #[test]
+        fn lens_rays_converge_on_focal_plane() {
+            let mut c = Camera::new(201.0, 101.0, PI / 2.0).unwrap();
+            c.aperture = 0.5;
+            c.focal_distance = 4.0;
+            let center = c.lens_ray_for_pixel(30.0, 70.0, 0.0, 0.0);
+            let edge = c.lens_ray_for_pixel(30.0, 70.0, 1.0, 0.0);
+            assert_eq!(edge.origin, Point3::new(0.5, 0.0, 0.0));
+            let t_center = -4.0 / center.direction.z;
+            let t_edge = -4.0 / edge.direction.z;
+            assert_eq!(center.position(t_center), edge.position(t_edge));
+        }
+
+        #[test]
+        fn polygon_bokeh_stays_in_unit_disk() {
+            let bokeh = Bokeh::Polygon(6);
+            for i in 0..20 {
+                for j in 0..20 {
+                    let (x, y) = bokeh.sample(i as f64 / 20.0, j as f64 / 20.0);
+                    assert!(x * x + y * y <= 1.0001);
+                }
+            }
+            let (x, y) = bokeh.sample(0.0, 0.0);
+            assert_eq!((x, y), (0.0, 0.0));
+        }
+
+        #[test]
+        fn defocus_blurs_out_of_focus_objects() {
+            let light = crate::features::lights::Light::new(
+                Point3::new(-10.0, 10.0, -10.0),
+                Color::new(1.0, 1.0, 1.0),
+            );
+            let world = World::new(light, &[Shape::Sphere(Sphere::new())]);
+            let mut c = Camera::new(21.0, 21.0, PI / 3.0).unwrap();
+            c.set_transform(view_transformation(
+                Point3::new(0.0, 0.0, -5.0),
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+            let sharp = c.render(&world);
+            c.aperture = 0.8;
+            c.focal_distance = 20.0;
+            c.samples = 16;
+            c.seed = 9;
+            let blurred = c.render(&world);
+            assert_eq!(blurred, c.render(&world));
+            // the blurred silhouette spreads over more pixels than the sharp one
+            let lit = |image: &Canvas| {
+                (0..21)
+                    .filter(|x| image.pixel_at(*x, 10).unwrap().r > 0.0)
+                    .count()
+            };
+            assert!(lit(&blurred) > lit(&sharp));
+        }
+    }
+
+    #[cfg(test)]
+    mod motion_blur_tests {
+        use std::f64::consts::PI;
+
+        use crate::features::{
+            lights::Light,
+            motion::Motion,
+            shape::Shape,
+            spheres::Sphere,
+            transformations::{translation, view_transformation},
+        };
+
+        use super::*;
+
+        #[test]
+        fn sampled_rays_fall_inside_the_shutter_interval() {
+            let mut c = Camera::new(11.0, 11.0, PI / 2.0).unwrap();
+            c.shutter_open = 0.25;
+            c.shutter_close = 0.75;
+            assert_eq!(c.ray_for_pixel(5.0, 5.0).time, 0.25);
+            let mut rng = c.pixel_rng(5, 5);
+            for _ in 0..50 {
+                let r = c.sample_ray(5.0, 5.0, &mut rng);
+                assert!(r.time >= 0.25 && r.time < 0.75);
+            }
+        }
+
+        #[test]
+        fn moving_sphere_streaks_across_the_frame() {
+            let light = Light::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let mut sphere = Shape::Sphere(Sphere::new());
+            sphere.set_motion(Some(Motion::linear(
+                translation(-1.0, 0.0, 0.0),
+                translation(1.0, 0.0, 0.0),
+            )));
+            let world = World::new(light, &[sphere]);
+            let mut c = Camera::new(21.0, 21.0, PI / 3.0).unwrap();
+            c.set_transform(view_transformation(
+                Point3::new(0.0, 0.0, -5.0),
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+            c.samples = 8;
+            let still = c.render(&world);
+            c.shutter_close = 1.0;
+            let blurred = c.render(&world);
+            assert_eq!(blurred, c.render(&world));
+            let lit = |image: &Canvas| {
+                (0..21)
+                    .filter(|x| image.pixel_at(*x, 10).unwrap().r > 0.0)
+                    .count()
+            };
+            assert!(lit(&blurred) > lit(&still));
+        }
+
+        #[test]
+        fn a_single_sample_still_sees_the_shutter() {
+            let light = Light::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let mut sphere = Shape::Sphere(Sphere::new());
+            sphere.set_motion(Some(Motion::linear(
+                translation(-1.0, 0.0, 0.0),
+                translation(1.0, 0.0, 0.0),
+            )));
+            let world = World::new(light, &[sphere]);
+            let mut c = Camera::new(21.0, 21.0, PI / 3.0).unwrap();
+            c.set_transform(view_transformation(
+                Point3::new(0.0, 0.0, -5.0),
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+            let still = c.render(&world);
+            c.shutter_close = 1.0;
+            let blurred = c.render(&world);
+            assert_eq!(blurred, c.render(&world));
+            assert_ne!(blurred, still);
+        }
+    }
+
+    #[cfg(test)]
+    mod projection_tests {
+        use std::f64::consts::PI;
+
+        use crate::features::transformations::translation;
+
+        use super::*;
+
+        #[test]
+        fn perspective_is_the_default() {
+            assert_eq!(
+                Camera::new(10.0, 10.0, PI / 2.0).unwrap().projection,
+                Projection::Perspective
+            );
+        }
+
+        #[test]
+        fn orthographic_rays_are_parallel() {
+            let mut c = Camera::orthographic(200.0, 100.0, 4.0).unwrap();
+            c.set_transform(translation(0.0, 0.0, 5.0)).unwrap();
+            assert_eq!(c.pixel_size, 0.02);
+            let center = c.ray_for_pixel(99.5, 49.5);
+            assert_eq!(center.origin, Point3::new(0.0, 0.0, -5.0));
+            assert_eq!(center.direction, Vector3::new(0.0, 0.0, -1.0));
+            let corner = c.ray_for_pixel(-0.5, -0.5);
+            assert_eq!(corner.origin, Point3::new(2.0, 1.0, -5.0));
+            assert_eq!(corner.direction, Vector3::new(0.0, 0.0, -1.0));
+        }
+
+        #[test]
+        fn fisheye_angle_grows_with_distance_from_center() {
+            let c = Camera::fisheye(100.0, 100.0, PI).unwrap();
+            let center = c.ray_for_pixel(49.5, 49.5);
+            assert_eq!(center.direction, Vector3::new(0.0, 0.0, -1.0));
+            // the edge of the wider side looks 90 degrees off axis
+            let edge = c.ray_for_pixel(-0.5, 49.5);
+            assert_eq!(edge.direction, Vector3::new(1.0, 0.0, 0.0));
+            let halfway = c.ray_for_pixel(24.5, 49.5);
+            let h = 2.0_f64.sqrt() / 2.0;
+            assert_eq!(halfway.direction, Vector3::new(h, 0.0, -h));
+        }
+
+        #[test]
+        fn equirectangular_covers_the_whole_sphere() {
+            let c = Camera::equirectangular(360.0, 180.0).unwrap();
+            let forward = c.ray_for_pixel(179.5, 89.5);
+            assert_eq!(forward.direction, Vector3::new(0.0, 0.0, -1.0));
+            let left = c.ray_for_pixel(89.5, 89.5);
+            assert_eq!(left.direction, Vector3::new(1.0, 0.0, 0.0));
+            let behind = c.ray_for_pixel(-0.5, 89.5);
+            assert_eq!(behind.direction, Vector3::new(0.0, 0.0, 1.0));
+            let up = c.ray_for_pixel(179.5, -0.5);
+            assert_eq!(up.direction, Vector3::new(0.0, 1.0, 0.0));
+        }
+
+        #[test]
+        fn orthographic_lens_rays_focus_on_the_focal_plane() {
+            let mut c = Camera::orthographic(10.0, 10.0, 2.0).unwrap();
+            c.aperture = 0.5;
+            c.focal_distance = 3.0;
+            let edge = c.lens_ray_for_pixel(2.0, 7.0, 1.0, 0.0);
+            let pinhole = c.ray_for_pixel(2.0, 7.0);
+            let t = -3.0 / edge.direction.z;
+            assert_eq!(edge.position(t), pinhole.position(3.0));
         }
     }
 }
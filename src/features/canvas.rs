@@ -1,9 +1,15 @@
-use super::tuple::Tuple;
+use std::{fs, io, path::Path};
 
+use super::{
+    error::{Error, Result},
+    tuple::Color,
+};
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
-    pub canvas: Vec<Vec<Tuple>>,
+    pub canvas: Vec<Vec<Color>>,
 }
 
 impl Canvas {
@@ -11,31 +17,240 @@ impl Canvas {
         Canvas {
             width,
             height,
-            canvas: vec![vec![Tuple::default_color(); width]; height],
+            canvas: vec![vec![Color::black(); width]; height],
         }
     }
-    pub fn write_pixel(&mut self, x: usize, y: usize, color: Tuple) {
+    pub fn write_pixel(&mut self, x: usize, y: usize, color: Color) -> Result<()> {
+        self.check_bounds(x, y)?;
         self.canvas[y][x] = color;
+        Ok(())
+    }
+    pub fn pixel_at(&self, x: usize, y: usize) -> Result<Color> {
+        self.check_bounds(x, y)?;
+        Ok(self.canvas[y][x])
+    }
+    fn check_bounds(&self, x: usize, y: usize) -> Result<()> {
+        if x >= self.width || y >= self.height {
+            return Err(Error::PixelOutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn read_ppm(path: impl AsRef<Path>) -> Result<Self> {
+        let data = fs::read(path)?;
+        Self::from_ppm(&data).ok_or(Error::MalformedImage("ppm"))
     }
-    pub fn pixel_at(&self, x: usize, y: usize) -> Tuple {
-        self.canvas[y][x]
+
+    // accepts both the plain (P3) and the binary (P6) flavour
+    pub fn from_ppm(data: &[u8]) -> Option<Self> {
+        let mut pos = 0;
+        let magic = ppm_token(data, &mut pos)?;
+        let width: usize = ppm_token(data, &mut pos)?.parse().ok()?;
+        let height: usize = ppm_token(data, &mut pos)?.parse().ok()?;
+        let max: u16 = ppm_token(data, &mut pos)?.parse().ok()?;
+        if max == 0 {
+            return None;
+        }
+        let max = max as f64;
+        let len = image_size(width, height)? * 3;
+        let mut samples = Vec::with_capacity(len);
+        match magic.as_str() {
+            "P3" => {
+                for _ in 0..len {
+                    samples.push(ppm_token(data, &mut pos)?.parse::<f64>().ok()?);
+                }
+            }
+            "P6" => {
+                // a single whitespace byte separates the header from the raster
+                pos += 1;
+                if max > 255.0 {
+                    // samples above 255 take two bytes, most significant first
+                    let raster = data.get(pos..pos + len * 2)?;
+                    samples.extend(
+                        raster
+                            .chunks(2)
+                            .map(|b| u16::from_be_bytes([b[0], b[1]]) as f64),
+                    );
+                } else {
+                    let raster = data.get(pos..pos + len)?;
+                    samples.extend(raster.iter().map(|b| *b as f64));
+                }
+            }
+            _ => return None,
+        }
+        let mut canvas = Canvas::new(width, height);
+        for (ix, rgb) in samples.chunks(3).enumerate() {
+            canvas.canvas[ix / width][ix % width] =
+                Color::new(rgb[0] / max, rgb[1] / max, rgb[2] / max);
+        }
+        Some(canvas)
+    }
+
+    pub fn read_hdr(path: impl AsRef<Path>) -> Result<Self> {
+        let data = fs::read(path)?;
+        Self::from_hdr(&data).ok_or(Error::MalformedImage("hdr"))
+    }
+
+    // Radiance RGBE, flat or run-length encoded, in the usual -Y h +X w
+    // orientation; colors are kept unclamped
+    pub fn from_hdr(data: &[u8]) -> Option<Self> {
+        let mut pos = 0;
+        let magic = hdr_line(data, &mut pos)?;
+        if !magic.starts_with("#?") {
+            return None;
+        }
+        // header variables run up to the first blank line
+        while !hdr_line(data, &mut pos)?.is_empty() {}
+        let resolution = hdr_line(data, &mut pos)?;
+        let fields: Vec<&str> = resolution.split_whitespace().collect();
+        let ["-Y", height, "+X", width] = fields[..] else {
+            return None;
+        };
+        let (width, height): (usize, usize) = (width.parse().ok()?, height.parse().ok()?);
+        image_size(width, height)?;
+        let mut canvas = Canvas::new(width, height);
+        let mut scanline = vec![[0u8; 4]; width];
+        for y in 0..height {
+            hdr_scanline(data, &mut pos, &mut scanline)?;
+            for (x, rgbe) in scanline.iter().enumerate() {
+                canvas.canvas[y][x] = rgbe_color(rgbe);
+            }
+        }
+        Some(canvas)
     }
 
     pub fn to_ppm(&self) {
-        print!("P3\n{} {}\n255\n", self.width, self.height);
-        for line in self.canvas.clone().into_iter() {
+        print!("{}", self.ppm());
+    }
+
+    // the plain (P3) encoding of the image
+    pub fn ppm(&self) -> String {
+        let mut out = format!("P3\n{} {}\n255\n", self.width, self.height);
+        for line in self.canvas.iter() {
             for pixel in line {
-                print!("{}", pixel.clamp().as_str())
+                out.push_str(&pixel.clamp().as_str());
+            }
+        }
+        out
+    }
+
+    pub fn write_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.ppm())
+    }
+}
+
+// the largest image the readers will allocate, 8192 by 8192 pixels; a
+// header claiming more is treated as corrupt rather than trusted
+const MAX_IMAGE_PIXELS: usize = 1 << 26;
+
+// the pixel count of a width by height image, if it is a sane size
+fn image_size(width: usize, height: usize) -> Option<usize> {
+    width
+        .checked_mul(height)
+        .filter(|pixels| *pixels <= MAX_IMAGE_PIXELS)
+}
+
+fn hdr_line(data: &[u8], pos: &mut usize) -> Option<String> {
+    let rest = data.get(*pos..)?;
+    let len = rest.iter().position(|b| *b == b'\n')?;
+    *pos += len + 1;
+    Some(String::from_utf8_lossy(&rest[..len]).trim().to_string())
+}
+
+fn hdr_scanline(data: &[u8], pos: &mut usize, out: &mut [[u8; 4]]) -> Option<()> {
+    let width = out.len();
+    let head = data.get(*pos..*pos + 4)?;
+    let run_length = (8..0x8000).contains(&width) && head[0] == 2 && head[1] == 2 && head[2] < 128;
+    if !run_length {
+        for rgbe in out.iter_mut() {
+            rgbe.copy_from_slice(data.get(*pos..*pos + 4)?);
+            *pos += 4;
+        }
+        return Some(());
+    }
+    if ((head[2] as usize) << 8 | head[3] as usize) != width {
+        return None;
+    }
+    *pos += 4;
+    // each channel is stored on its own, as runs and literal stretches
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let count = *data.get(*pos)? as usize;
+            *pos += 1;
+            if count > 128 {
+                let value = *data.get(*pos)?;
+                *pos += 1;
+                for rgbe in out.get_mut(x..x + count - 128)? {
+                    rgbe[channel] = value;
+                }
+                x += count - 128;
+            } else {
+                if count == 0 {
+                    return None;
+                }
+                for (rgbe, value) in out
+                    .get_mut(x..x + count)?
+                    .iter_mut()
+                    .zip(data.get(*pos..*pos + count)?)
+                {
+                    rgbe[channel] = *value;
+                }
+                *pos += count;
+                x += count;
             }
         }
     }
+    Some(())
+}
+
+fn rgbe_color(rgbe: &[u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::black();
+    }
+    // the mantissas are fractions of 256 scaled by 2^(e - 128)
+    let scale = 2f64.powi(rgbe[3] as i32 - 136);
+    Color::new(
+        rgbe[0] as f64 * scale,
+        rgbe[1] as f64 * scale,
+        rgbe[2] as f64 * scale,
+    )
+}
+
+fn ppm_token(data: &[u8], pos: &mut usize) -> Option<String> {
+    loop {
+        while *pos < data.len() && data[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if data.get(*pos) != Some(&b'#') {
+            break;
+        }
+        while *pos < data.len() && data[*pos] != b'\n' {
+            *pos += 1;
+        }
+    }
+    let start = *pos;
+    while *pos < data.len() && !data[*pos].is_ascii_whitespace() {
+        *pos += 1;
+    }
+    if start == *pos {
+        return None;
+    }
+    String::from_utf8(data[start..*pos].to_vec()).ok()
 }
 
 #[cfg(test)]
 mod canvas_tests {
-    use crate::features::tuple::Tuple;
+    use std::fs;
+
+    use crate::features::tuple::Color;
 
-    use super::Canvas;
+    use super::{Canvas, Error};
 
     #[test]
     fn test_canvas_new() {
@@ -47,16 +262,116 @@ mod canvas_tests {
     #[test]
     fn test_write_pixel() {
         let mut canvas = Canvas::new(10, 20);
-        let red = Tuple::color(1.0, 0.0, 0.0);
-        canvas.write_pixel(2, 3, red);
-        assert!(canvas.pixel_at(2, 3) == red);
+        let red = Color::new(1.0, 0.0, 0.0);
+        canvas.write_pixel(2, 3, red).unwrap();
+        assert!(canvas.pixel_at(2, 3).unwrap() == red);
+    }
+
+    #[test]
+    fn pixels_outside_the_canvas_are_errors() {
+        let mut canvas = Canvas::new(10, 20);
+        let red = Color::new(1.0, 0.0, 0.0);
+        assert!(matches!(
+            canvas.write_pixel(10, 3, red),
+            Err(Error::PixelOutOfBounds { x: 10, y: 3, .. })
+        ));
+        assert!(canvas.pixel_at(2, 20).is_err());
+        assert_eq!(canvas, Canvas::new(10, 20));
     }
 
     #[test]
     fn test_canvas_to_ppm() {
         let mut canvas = Canvas::new(4, 4);
-        let red = Tuple::color(1.0, 0.0, 0.0);
-        canvas.write_pixel(2, 3, red);
+        let red = Color::new(1.0, 0.0, 0.0);
+        canvas.write_pixel(2, 3, red).unwrap();
         canvas.to_ppm();
     }
+
+    #[test]
+    fn test_reading_plain_ppm() {
+        let ppm = "P3\n# a comment\n2 1\n255\n255 0 0\n0 0 255\n";
+        let canvas = Canvas::from_ppm(ppm.as_bytes()).unwrap();
+        assert_eq!(canvas.width, 2);
+        assert_eq!(canvas.height, 1);
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(canvas.pixel_at(1, 0).unwrap(), Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn reading_flat_hdr() {
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 2 +X 1\n".to_vec();
+        hdr.extend([128, 64, 32, 129, 0, 0, 0, 0]);
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+        assert_eq!((canvas.width, canvas.height), (1, 2));
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(1.0, 0.5, 0.25));
+        assert_eq!(canvas.pixel_at(0, 1).unwrap(), Color::black());
+    }
+
+    #[test]
+    fn reading_run_length_encoded_hdr() {
+        let mut hdr = b"#?RGBE\n\n-Y 1 +X 8\n".to_vec();
+        hdr.extend([2, 2, 0, 8]);
+        // red: one run of 8
+        hdr.extend([136, 128]);
+        // green: a literal stretch of 8
+        hdr.extend([8, 0, 32, 64, 96, 128, 160, 192, 224]);
+        // blue: a run of 3 and a literal stretch of 5
+        hdr.extend([131, 0, 5, 1, 2, 3, 4, 5]);
+        // exponent: one run of 8, a scale of 1/8
+        hdr.extend([136, 133]);
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(16.0, 0.0, 0.0));
+        assert_eq!(
+            canvas.pixel_at(7, 0).unwrap(),
+            Color::new(16.0, 28.0, 0.625)
+        );
+        assert!(Canvas::from_hdr(&hdr[..hdr.len() - 1]).is_none());
+        assert!(Canvas::from_hdr(b"P3\n1 1\n255\n0 0 0\n").is_none());
+        assert!(Canvas::from_hdr(b"#?RGBE\n\n-Y 100000 +X 100000\n").is_none());
+    }
+
+    #[test]
+    fn test_reading_binary_ppm() {
+        let mut ppm = b"P6\n1 2\n255\n".to_vec();
+        ppm.extend([0, 255, 0, 51, 102, 153]);
+        let canvas = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(canvas.pixel_at(0, 1).unwrap(), Color::new(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn test_reading_sixteen_bit_binary_ppm() {
+        let mut ppm = b"P6\n2 1\n65535\n".to_vec();
+        ppm.extend([0xff, 0xff, 0x00, 0x00, 0x80, 0x00]);
+        ppm.extend([0x00, 0x00, 0x33, 0x33, 0xff, 0xff]);
+        let canvas = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(canvas.pixel_at(0, 0).unwrap(), Color::new(1.0, 0.0, 0.5));
+        assert_eq!(canvas.pixel_at(1, 0).unwrap(), Color::new(0.0, 0.2, 1.0));
+        // one byte per sample is too short for a 16-bit raster
+        assert!(Canvas::from_ppm(&ppm[..ppm.len() - 6]).is_none());
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0)).unwrap();
+        canvas.write_pixel(2, 1, Color::new(0.0, 1.0, 1.0)).unwrap();
+        assert!(canvas.ppm().starts_with("P3\n3 2\n255\n255 0 0\n"));
+        assert_eq!(Canvas::from_ppm(canvas.ppm().as_bytes()), Some(canvas));
+    }
+
+    #[test]
+    fn test_reading_malformed_ppm() {
+        assert!(Canvas::from_ppm(b"P3\n2 2\n255\n1 2 3").is_none());
+        assert!(Canvas::from_ppm(b"P5\n1 1\n255\n0").is_none());
+        // headers are not trusted with the allocation or the scale
+        assert!(Canvas::from_ppm(b"P3\n100000 100000\n255\n0 0 0").is_none());
+        assert!(Canvas::from_ppm(b"P6\n18446744073709551615 2\n255\n\0").is_none());
+        assert!(Canvas::from_ppm(b"P3\n1 1\n0\n0 0 0").is_none());
+        let path = std::env::temp_dir().join(format!("malformed-{}.ppm", std::process::id()));
+        fs::write(&path, "P3\n2 2\n255\n1 2 3").unwrap();
+        let read = Canvas::read_ppm(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(read, Err(Error::MalformedImage("ppm"))));
+    }
 }
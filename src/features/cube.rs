@@ -1,34 +1,38 @@
-use std::f32::INFINITY;
-
-use uuid::Uuid;
-
-use crate::features::shape::Shape;
-
 use super::{
-    intersections::{self, intersections, Intersection},
     materials::Material,
-    matrice::Matrice,
+    motion::Motion,
     rays::Ray,
-    tuple::Tuple,
+    shape::Primitive,
+    transformations::Transform,
+    tuple::{Point3, Vector3, EPSILON},
 };
 
 #[derive(PartialEq, Debug, Clone, Eq)]
 pub struct Cube {
-    pub id: Uuid,
-    pub transform: Matrice,
+    transform: Transform,
+    pub motion: Option<Motion>,
     pub material: Material,
 }
 
+impl Default for Cube {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Cube {
     pub fn new() -> Self {
         Self {
             material: Material::new(),
-            id: Uuid::new_v4(),
-            transform: Matrice::identity_matrix(4),
+            transform: Transform::default(),
+            motion: None,
         }
     }
+}
 
-    pub fn intersect(&self, r: &Ray) -> Vec<Intersection> {
+// the axis-aligned cube from -1 to 1 on every axis
+impl Primitive for Cube {
+    fn local_intersect(&self, r: &Ray) -> Vec<f64> {
         let (xtmin, xtmax) = check_axis(r.origin.x, r.direction.x);
         let (ytmin, ytmax) = check_axis(r.origin.y, r.direction.y);
         let (ztmin, ztmax) = check_axis(r.origin.z, r.direction.z);
@@ -43,33 +47,71 @@ impl Cube {
         if tmin > tmax {
             return vec![];
         }
-        vec![
-            Intersection::new(tmin, Shape::Cube(self.clone())),
-            Intersection::new(tmax, Shape::Cube(self.clone())),
-        ]
+        vec![tmin, tmax]
     }
 
-    pub fn normal_at(&self, point: Tuple) -> Tuple {
+    fn local_normal_at(&self, point: Point3) -> Vector3 {
         let maxc = [point.x.abs(), point.y.abs(), point.z.abs()]
             .into_iter()
             .max_by(|x, y| x.total_cmp(y))
             .unwrap();
         if maxc == point.x.abs() {
-            self.transform.clone() * Tuple::vector(point.x, 0.0, 0.0)
+            Vector3::new(point.x, 0.0, 0.0)
         } else if maxc == point.y.abs() {
-            self.transform.clone() * Tuple::vector(0.0, point.y, 0.0)
+            Vector3::new(0.0, point.y, 0.0)
         } else {
-            self.transform.clone() * Tuple::vector(0.0, 0.0, point.z)
+            Vector3::new(0.0, 0.0, point.z)
         }
     }
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+    fn transformation_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn material(&self) -> &Material {
+        &self.material
+    }
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+    fn motion_mut(&mut self) -> &mut Option<Motion> {
+        &mut self.motion
+    }
+    fn local_sample(&self, u: f64, v: f64) -> Option<(Point3, Vector3, f64)> {
+        let face = ((u * 6.0) as usize).min(5);
+        let a = (u * 6.0 - face as f64) * 2.0 - 1.0;
+        let b = v * 2.0 - 1.0;
+        let sign = if face.is_multiple_of(2) { 1.0 } else { -1.0 };
+        let (point, normal) = match face / 2 {
+            0 => (Point3::new(sign, a, b), Vector3::new(sign, 0.0, 0.0)),
+            1 => (Point3::new(a, sign, b), Vector3::new(0.0, sign, 0.0)),
+            _ => (Point3::new(a, b, sign), Vector3::new(0.0, 0.0, sign)),
+        };
+        Some((point, normal, 24.0))
+    }
+    fn local_uv(&self, point: Point3) -> (f64, f64) {
+        let abs = [point.x.abs(), point.y.abs(), point.z.abs()];
+        let (a, b) = if abs[0] >= abs[1] && abs[0] >= abs[2] {
+            (point.z, point.y)
+        } else if abs[1] >= abs[2] {
+            (point.x, point.z)
+        } else {
+            (point.x, point.y)
+        };
+        ((a + 1.0) / 2.0, (b + 1.0) / 2.0)
+    }
 }
 
-fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
+fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
     let tmin_numerator = -1.0 - origin;
     let tmax_numerator = 1.0 - origin;
-    let mut tmin = tmin_numerator * INFINITY;
-    let mut tmax = tmax_numerator * INFINITY;
-    if direction.abs() >= 0.0001 {
+    let mut tmin = tmin_numerator * f64::INFINITY;
+    let mut tmax = tmax_numerator * f64::INFINITY;
+    if direction.abs() >= EPSILON {
         tmin = tmin_numerator / direction;
         tmax = tmax_numerator / direction;
     }
@@ -81,7 +123,7 @@ fn check_axis(origin: f32, direction: f32) -> (f32, f32) {
 
 #[cfg(test)]
 mod cube_tests {
-    use crate::features::tuple::Tuple;
+    use crate::features::tuple::{Point3, Vector3};
 
     use super::*;
 
@@ -90,53 +132,53 @@ mod cube_tests {
         let c = Cube::new();
         let test_cases = [
             (
-                Tuple::point(5.0, 0.5, 0.0),
-                Tuple::vector(-1.0, 0.0, 0.0),
+                Point3::new(5.0, 0.5, 0.0),
+                Vector3::new(-1.0, 0.0, 0.0),
                 4.0,
                 6.0,
             ),
             (
-                Tuple::point(-5.0, 0.5, 0.0),
-                Tuple::vector(1.0, 0.0, 0.0),
+                Point3::new(-5.0, 0.5, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
                 4.0,
                 6.0,
             ),
             (
-                Tuple::point(0.5, 5.0, 0.0),
-                Tuple::vector(0.0, -1.0, 0.0),
+                Point3::new(0.5, 5.0, 0.0),
+                Vector3::new(0.0, -1.0, 0.0),
                 4.0,
                 6.0,
             ),
             (
-                Tuple::point(0.5, -5.0, 0.0),
-                Tuple::vector(0.0, 1.0, 0.0),
+                Point3::new(0.5, -5.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
                 4.0,
                 6.0,
             ),
             (
-                Tuple::point(0.5, 0.0, 5.0),
-                Tuple::vector(0.0, 0.0, -1.0),
+                Point3::new(0.5, 0.0, 5.0),
+                Vector3::new(0.0, 0.0, -1.0),
                 4.0,
                 6.0,
             ),
             (
-                Tuple::point(0.5, 0.0, -5.0),
-                Tuple::vector(0.0, 0.0, 1.0),
+                Point3::new(0.5, 0.0, -5.0),
+                Vector3::new(0.0, 0.0, 1.0),
                 4.0,
                 6.0,
             ),
             (
-                Tuple::point(0.0, 0.5, 0.0),
-                Tuple::vector(0.0, 0.0, 1.0),
+                Point3::new(0.0, 0.5, 0.0),
+                Vector3::new(0.0, 0.0, 1.0),
                 -1.0,
                 1.0,
             ),
         ];
         for case in test_cases {
-            let xs = c.intersect(&Ray::new(case.0, case.1));
+            let xs = c.local_intersect(&Ray::new(case.0, case.1));
             assert_eq!(xs.len(), 2);
-            assert_eq!(xs[0].t, case.2);
-            assert_eq!(xs[1].t, case.3);
+            assert_eq!(xs[0], case.2);
+            assert_eq!(xs[1], case.3);
         }
     }
 
@@ -145,23 +187,23 @@ mod cube_tests {
         let c = Cube::new();
         let test_cases = [
             (
-                Tuple::point(-2.0, 0.0, 0.0),
-                Tuple::vector(0.2673, 0.5345, 0.8018),
+                Point3::new(-2.0, 0.0, 0.0),
+                Vector3::new(0.2673, 0.5345, 0.8018),
             ),
             (
-                Tuple::point(0.0, -2.0, 0.0),
-                Tuple::vector(0.8018, 0.2673, 0.5345),
+                Point3::new(0.0, -2.0, 0.0),
+                Vector3::new(0.8018, 0.2673, 0.5345),
             ),
             (
-                Tuple::point(0.0, 0.0, -2.0),
-                Tuple::vector(0.5345, 0.8018, 0.2673),
+                Point3::new(0.0, 0.0, -2.0),
+                Vector3::new(0.5345, 0.8018, 0.2673),
             ),
-            (Tuple::point(2.0, 0.0, 2.0), Tuple::vector(0.0, 0.0, -1.0)),
-            (Tuple::point(0.0, 2.0, 2.0), Tuple::vector(0.0, -1.0, 0.0)),
-            (Tuple::point(2.0, 2.0, 0.0), Tuple::vector(-1.0, 0.0, 0.0)),
+            (Point3::new(2.0, 0.0, 2.0), Vector3::new(0.0, 0.0, -1.0)),
+            (Point3::new(0.0, 2.0, 2.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Point3::new(2.0, 2.0, 0.0), Vector3::new(-1.0, 0.0, 0.0)),
         ];
         for case in test_cases {
-            let xs = c.intersect(&Ray::new(case.0, case.1));
+            let xs = c.local_intersect(&Ray::new(case.0, case.1));
             assert_eq!(xs.len(), 0);
         }
     }
@@ -170,17 +212,14 @@ mod cube_tests {
     fn normal_on_surface_of_cube() {
         let c = Cube::new();
         let test_cases = [
-            (Tuple::point(1.0, 0.5, -0.8), Tuple::vector(1.0, 0.0, 0.0)),
-            (Tuple::point(-1.0, -0.2, 0.9), Tuple::vector(-1.0, 0.0, 0.0)),
-            (Tuple::point(-0.4, 1.0, -0.1), Tuple::vector(0.0, 1.0, 0.0)),
-            (Tuple::point(0.3, -1.0, -0.7), Tuple::vector(0.0, -1.0, 0.0)),
-            (Tuple::point(-0.6, 0.3, 1.0), Tuple::vector(0.0, 0.0, 1.0)),
-            (Tuple::point(0.4, 0.4, -1.0), Tuple::vector(0.0, 0.0, -1.0)),
-            (Tuple::point(1.0, 1.0, 1.0), Tuple::vector(1.0, 0.0, 0.0)),
-            (
-                Tuple::point(-1.0, -1.0, -1.0),
-                Tuple::vector(-1.0, 0.0, 0.0),
-            ),
+            (Point3::new(1.0, 0.5, -0.8), Vector3::new(1.0, 0.0, 0.0)),
+            (Point3::new(-1.0, -0.2, 0.9), Vector3::new(-1.0, 0.0, 0.0)),
+            (Point3::new(-0.4, 1.0, -0.1), Vector3::new(0.0, 1.0, 0.0)),
+            (Point3::new(0.3, -1.0, -0.7), Vector3::new(0.0, -1.0, 0.0)),
+            (Point3::new(-0.6, 0.3, 1.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Point3::new(0.4, 0.4, -1.0), Vector3::new(0.0, 0.0, -1.0)),
+            (Point3::new(1.0, 1.0, 1.0), Vector3::new(1.0, 0.0, 0.0)),
+            (Point3::new(-1.0, -1.0, -1.0), Vector3::new(-1.0, 0.0, 0.0)),
         ];
         for case in test_cases {
             let normal = c.normal_at(case.0);
@@ -0,0 +1,182 @@
+use std::{f64::consts::PI, path::Path, sync::Arc};
+
+use super::{
+    background::{equirectangular_direction, equirectangular_uv, read_image, texel, Background},
+    canvas::Canvas,
+    error::Result,
+    tuple::{Color, Vector3},
+};
+
+// An equirectangular image that lights the scene from infinitely far away,
+// such as a studio HDRI. Directions are drawn in proportion to the light
+// arriving from them, so a small bright window is found with few samples.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Environment {
+    image: Arc<Canvas>,
+    // running sums of the row weights, scaled to end at 1
+    rows: Vec<f64>,
+    // per row, running sums of the pixel weights, scaled to end at 1
+    columns: Vec<Vec<f64>>,
+    // the weight of every pixel, and their total
+    weights: Vec<Vec<f64>>,
+    total: f64,
+}
+
+impl Environment {
+    pub fn new(image: Canvas) -> Self {
+        let (width, height) = (image.width, image.height);
+        // rows near the poles cover less of the sphere
+        let weights: Vec<Vec<f64>> = (0..height)
+            .map(|y| {
+                let latitude = PI * (0.5 - (y as f64 + 0.5) / height as f64);
+                (0..width)
+                    .map(|x| image.canvas[y][x].luminance().max(0.0) * latitude.cos())
+                    .collect()
+            })
+            .collect();
+        let row_sums: Vec<f64> = weights.iter().map(|row| row.iter().sum()).collect();
+        let total = row_sums.iter().sum();
+        Self {
+            image: Arc::new(image),
+            rows: running_sums(&row_sums),
+            columns: weights.iter().map(|row| running_sums(row)).collect(),
+            weights,
+            total,
+        }
+    }
+
+    // a `.hdr` or `.ppm` image
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(read_image(path.as_ref())?))
+    }
+
+    // the same image as a background, so the lights are also seen
+    pub fn background(&self) -> Background {
+        Background::Equirectangular(self.image.clone())
+    }
+
+    pub fn radiance(&self, direction: &Vector3) -> Color {
+        let (u, v) = equirectangular_uv(direction);
+        texel(&self.image, u, v)
+    }
+
+    // a direction drawn from two numbers in [0, 1), the light arriving from
+    // it and its probability density over solid angle; None for an image
+    // that gives no light
+    pub fn sample(&self, u1: f64, u2: f64) -> Option<(Vector3, Color, f64)> {
+        if self.total <= 0.0 {
+            return None;
+        }
+        let (y, fy) = pick(&self.rows, u1);
+        let (x, fx) = pick(&self.columns[y], u2);
+        let u = (x as f64 + fx) / self.image.width as f64;
+        let v = (y as f64 + fy) / self.image.height as f64;
+        let direction = equirectangular_direction(u, v);
+        let pdf = self.pdf_at(x, y, v);
+        Some((direction, self.image.canvas[y][x], pdf))
+    }
+
+    // the density `sample` draws `direction` with
+    pub fn pdf(&self, direction: &Vector3) -> f64 {
+        if self.total <= 0.0 {
+            return 0.0;
+        }
+        let (u, v) = equirectangular_uv(direction);
+        let x = ((u * self.image.width as f64) as usize).min(self.image.width - 1);
+        let y = ((v * self.image.height as f64) as usize).min(self.image.height - 1);
+        self.pdf_at(x, y, v)
+    }
+
+    fn pdf_at(&self, x: usize, y: usize, v: f64) -> f64 {
+        let cos_latitude = (PI * (0.5 - v)).cos();
+        if cos_latitude <= 0.0 {
+            return 0.0;
+        }
+        // a pixel spans 2 PI / width by PI / height, shrunk by cos(latitude)
+        let pixels = (self.image.width * self.image.height) as f64;
+        self.weights[y][x] / self.total * pixels / (2.0 * PI * PI * cos_latitude)
+    }
+}
+
+fn running_sums(weights: &[f64]) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    let mut sum = 0.0;
+    weights
+        .iter()
+        .map(|w| {
+            sum += w;
+            if total > 0.0 {
+                sum / total
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+// the bin `u` falls into and how far into it, skipping empty bins
+fn pick(cdf: &[f64], u: f64) -> (usize, f64) {
+    let ix = cdf.partition_point(|c| *c <= u).min(cdf.len() - 1);
+    let low = if ix == 0 { 0.0 } else { cdf[ix - 1] };
+    let width = cdf[ix] - low;
+    let offset = if width > 0.0 { (u - low) / width } else { 0.5 };
+    (ix, offset.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod environment_tests {
+    use super::*;
+
+    fn studio() -> Canvas {
+        // dim everywhere, with one bright window just above the horizon
+        let mut image = Canvas::new(16, 8);
+        for row in image.canvas.iter_mut() {
+            for pixel in row.iter_mut() {
+                *pixel = Color::new(0.01, 0.01, 0.01);
+            }
+        }
+        image.canvas[3][8] = Color::new(100.0, 90.0, 80.0);
+        image
+    }
+
+    #[test]
+    fn samples_favour_bright_pixels() {
+        let env = Environment::new(studio());
+        let mut bright = 0;
+        for ix in 0..100 {
+            for jx in 0..10 {
+                let (u1, u2) = ((ix as f64 + 0.5) / 100.0, (jx as f64 + 0.5) / 10.0);
+                let (direction, color, pdf) = env.sample(u1, u2).unwrap();
+                assert_eq!(env.radiance(&direction), color);
+                assert!((env.pdf(&direction) - pdf).abs() < 1e-9 * pdf.max(1.0));
+                if color.r > 1.0 {
+                    bright += 1;
+                }
+            }
+        }
+        assert!(bright > 900);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_over_the_sphere() {
+        let env = Environment::new(studio());
+        let (n, m) = (400, 200);
+        let mut sum = 0.0;
+        for ix in 0..n {
+            for jx in 0..m {
+                let (u, v) = ((ix as f64 + 0.5) / n as f64, (jx as f64 + 0.5) / m as f64);
+                let d = equirectangular_direction(u, v);
+                let solid_angle = 2.0 * PI * PI * (PI * (0.5 - v)).cos() / (n * m) as f64;
+                sum += env.pdf(&d) * solid_angle;
+            }
+        }
+        assert!((sum - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn black_image_gives_no_light() {
+        let env = Environment::new(Canvas::new(4, 2));
+        assert!(env.sample(0.5, 0.5).is_none());
+        assert_eq!(env.pdf(&Vector3::new(0.0, 1.0, 0.0)), 0.0);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{fmt, io};
+
+use super::{matrice::Matrice4, tuple::TupleType};
+
+// Everything that can go wrong while building or rendering a scene. Bad
+// input is caught where it enters, e.g. when a transform is assigned, rather
+// than surfacing as a panic halfway through a render.
+#[derive(Debug)]
+pub enum Error {
+    // a transform that squashes space flat, like a zero scale, has no inverse
+    // to take rays into object space with
+    SingularTransform(Box<Matrice4>),
+    // a tagged tuple converted into a type that holds a different kind
+    MismatchedTuple {
+        expected: TupleType,
+        found: TupleType,
+    },
+    PixelOutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    // the two views of a stereo pair must be the same size to be packed
+    MismatchedViews {
+        left: (usize, usize),
+        right: (usize, usize),
+    },
+    // a camera needs a positive, finite image size to divide into pixels
+    InvalidImageSize {
+        hsize: f64,
+        vsize: f64,
+    },
+    // the angle a camera sees, or the width for an orthographic one
+    InvalidFieldOfView(f64),
+    // a transform with an infinite or NaN entry, which would poison every
+    // ray taken through it
+    NonFiniteTransform(Box<Matrice4>),
+    // stereo eyes are toed in towards a point in front of the rig
+    InvalidConvergence(f64),
+    // an animation track needs at least one key to hold
+    EmptyTrack,
+    // frames are timed by dividing by the rate, so it must be positive
+    InvalidFrameRate(f64),
+    // an image file that could not be decoded, named by its format
+    MalformedImage(&'static str),
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::SingularTransform(m) => {
+                write!(
+                    f,
+                    "transform {:?} is singular and cannot be inverted",
+                    m.rows()
+                )
+            }
+            Error::MismatchedTuple { expected, found } => {
+                write!(f, "expected a {expected:?} tuple but found a {found:?}")
+            }
+            Error::PixelOutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(f, "pixel ({x}, {y}) is outside the {width}x{height} canvas"),
+            Error::MismatchedViews { left, right } => write!(
+                f,
+                "stereo views are {}x{} and {}x{}",
+                left.0, left.1, right.0, right.1
+            ),
+            Error::InvalidImageSize { hsize, vsize } => {
+                write!(f, "camera image size {hsize}x{vsize} is not positive")
+            }
+            Error::InvalidFieldOfView(fov) => {
+                write!(f, "field of view {fov} is out of range")
+            }
+            Error::NonFiniteTransform(m) => {
+                write!(
+                    f,
+                    "transform {:?} has entries that are not finite",
+                    m.rows()
+                )
+            }
+            Error::InvalidConvergence(d) => {
+                write!(f, "convergence distance {d} is not a positive number")
+            }
+            Error::EmptyTrack => write!(f, "an animation track needs at least one key"),
+            Error::InvalidFrameRate(fps) => {
+                write!(f, "frame rate {fps} is not a positive number")
+            }
+            Error::MalformedImage(format) => write!(f, "malformed {format} image"),
+            Error::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use crate::features::transformations::scaling;
+
+    use super::*;
+
+    #[test]
+    fn errors_explain_themselves() {
+        let e = Error::PixelOutOfBounds {
+            x: 4,
+            y: 1,
+            width: 4,
+            height: 2,
+        };
+        assert_eq!(e.to_string(), "pixel (4, 1) is outside the 4x2 canvas");
+        let e = Error::SingularTransform(Box::new(scaling(0.0, 1.0, 1.0)));
+        assert!(e.to_string().contains("singular"));
+        assert_eq!(
+            Error::MalformedImage("ppm").to_string(),
+            "malformed ppm image"
+        );
+        let e: Error = io::Error::new(io::ErrorKind::NotFound, "no such file").into();
+        assert!(std::error::Error::source(&e).is_some());
+    }
+}
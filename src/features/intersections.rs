@@ -1,87 +1,101 @@
+use std::ptr;
+
 use super::shape::Shape;
-#[derive(Clone, PartialEq, Debug)]
-pub struct Intersection {
-    pub t: f32,
-    pub s: Shape,
+
+// A crossing of a ray with a shape. The shape is borrowed from wherever it
+// lives, usually the world's arena, so an intersection is a float and a
+// pointer. Two intersections are on the same object only when they point at
+// the same shape, however alike two shapes look.
+#[derive(Clone, Copy, Debug)]
+pub struct Intersection<'a> {
+    pub t: f64,
+    pub object: &'a Shape,
+}
+
+impl<'a> Intersection<'a> {
+    pub fn new(t: f64, object: &'a Shape) -> Self {
+        Self { t, object }
+    }
+    pub fn is_on(&self, shape: &Shape) -> bool {
+        ptr::eq(self.object, shape)
+    }
 }
 
-impl Intersection {
-    pub fn new(t: f32, s: Shape) -> Self {
-        Self { t, s }
+impl PartialEq for Intersection<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.t == other.t && self.is_on(other.object)
     }
 }
 
-pub fn intersections(xs: &mut [Intersection]) -> Vec<Intersection> {
+pub fn intersections<'a>(xs: &mut [Intersection<'a>]) -> Vec<Intersection<'a>> {
     xs.sort_by(|a, b| a.t.total_cmp(&b.t));
     xs.to_vec()
 }
 
 pub fn hit(xs: Vec<Intersection>) -> Option<Intersection> {
-    for i in xs {
-        if i.t > 0.0 {
-            return Some(i.clone());
-        }
-    }
-    None
+    xs.into_iter().find(|i| i.t > 0.0)
 }
 
 #[cfg(test)]
 mod intersection_tests {
     use crate::features::shape::Shape;
     use crate::features::spheres::Sphere;
-    use crate::features::{rays::Ray, tuple::Tuple};
+    use crate::features::{
+        rays::Ray,
+        tuple::{Point3, Vector3},
+    };
 
     use super::Intersection;
 
     #[test]
     fn test_creating_intersection() {
         let s = Shape::Sphere(Sphere::new());
-        let i = Intersection::new(3.5, s.clone());
+        let i = Intersection::new(3.5, &s);
         assert_eq!(i.t, 3.5);
-        assert_eq!(i.s, s);
+        assert!(i.is_on(&s));
     }
 
     #[test]
     fn test_intersecting_sphere_two_points() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let s = Shape::Sphere(Sphere::new());
         let xs = s.intersect(&r);
         assert_eq!(xs.len(), 2);
         assert_eq!(xs[0].t, 4.0);
         assert_eq!(xs[1].t, 6.0);
-        assert_eq!(xs[0].s, s);
+        assert!(xs[0].is_on(&s));
     }
 
     #[test]
     fn test_intersecting_sphere_tangent() {
-        let r = Ray::new(Tuple::point(0.0, 1.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 1.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let s = Shape::Sphere(Sphere::new());
         let xs = s.intersect(&r);
         assert_eq!(xs.len(), 2);
         assert_eq!(xs[0].t, 5.0);
         assert_eq!(xs[1].t, 5.0);
-        assert_eq!(xs[0].s, s);
+        assert!(xs[0].is_on(&s));
     }
     #[test]
     fn test_no_intersection() {
-        let r = Ray::new(Tuple::point(0.0, 2.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 2.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let s = Shape::Sphere(Sphere::new());
         let xs = s.intersect(&r);
         assert_eq!(xs.len(), 0);
     }
     #[test]
     fn test_intersecting_inside_sphere() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
         let s = Shape::Sphere(Sphere::new());
         let xs = s.intersect(&r);
         assert_eq!(xs.len(), 2);
         assert_eq!(xs[0].t, -1.0);
         assert_eq!(xs[1].t, 1.0);
-        assert_eq!(xs[0].s, s);
+        assert!(xs[0].is_on(&s));
     }
     #[test]
     fn test_sphere_behind_ray() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, 5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
         let s = Shape::Sphere(Sphere::new());
         let xs = s.intersect(&r);
         assert_eq!(xs.len(), 2);
@@ -97,14 +111,14 @@ mod intersect_transformed_sphere_test {
         shape::Shape,
         spheres::Sphere,
         transformations::{scaling, translation},
-        tuple::Tuple,
+        tuple::{Point3, Vector3},
     };
 
     #[test]
     fn test_scaled_sphere() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let mut s = Shape::Sphere(Sphere::new());
-        s.set_transform(scaling(2.0, 2.0, 2.0));
+        s.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
         let xs = s.intersect(&r);
         assert_eq!(xs.len(), 2);
         assert_eq!(xs[0].t, 3.0);
@@ -113,9 +127,9 @@ mod intersect_transformed_sphere_test {
 
     #[test]
     fn test_translated_sphere() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let mut s = Shape::Sphere(Sphere::new());
-        s.set_transform(translation(5.0, 0.0, 0.0));
+        s.set_transform(translation(5.0, 0.0, 0.0)).unwrap();
         let xs = s.intersect(&r);
         assert_eq!(xs.len(), 0);
     }
@@ -134,26 +148,26 @@ mod hit_tests {
     #[test]
     fn test_all_intersections_have_positive_t() {
         let s = Shape::Sphere(Sphere::new());
-        let i1 = Intersection::new(1.0, s.clone());
-        let i2 = Intersection::new(2.0, s.clone());
-        let xs = intersections(&mut [i2, i1.clone()]);
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
+        let xs = intersections(&mut [i2, i1]);
         let i = hit(xs).unwrap();
-        assert!(i == i1.clone());
+        assert!(i == i1);
     }
     #[test]
     fn test_some_intersections_have_negative_t() {
         let s = Shape::Sphere(Sphere::new());
-        let i1 = Intersection::new(-1.0, s.clone());
-        let i2 = Intersection::new(1.0, s.clone());
-        let xs = intersections(&mut [i2.clone(), i1]);
+        let i1 = Intersection::new(-1.0, &s);
+        let i2 = Intersection::new(1.0, &s);
+        let xs = intersections(&mut [i2, i1]);
         let i = hit(xs).unwrap();
         assert!(i == i2);
     }
     #[test]
     fn test_all_intersections_have_negative_t() {
         let s = Shape::Sphere(Sphere::new());
-        let i1 = Intersection::new(-2.0, s.clone());
-        let i2 = Intersection::new(-1.0, s);
+        let i1 = Intersection::new(-2.0, &s);
+        let i2 = Intersection::new(-1.0, &s);
         let xs = intersections(&mut [i2, i1]);
         let i = hit(xs);
         assert!(i.is_none());
@@ -162,50 +176,67 @@ mod hit_tests {
     #[test]
     fn test_lowest_non_negative_t() {
         let s = Shape::Sphere(Sphere::new());
-        let i1 = Intersection::new(5.0, s.clone());
-        let i2 = Intersection::new(7.0, s.clone());
-        let i3 = Intersection::new(-3.0, s.clone());
-        let i4 = Intersection::new(2.0, s.clone());
-        let xs = intersections(&mut [i1, i2, i3, i4.clone()]);
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(7.0, &s);
+        let i3 = Intersection::new(-3.0, &s);
+        let i4 = Intersection::new(2.0, &s);
+        let xs = intersections(&mut [i1, i2, i3, i4]);
         let i = hit(xs).unwrap();
         assert!(i == i4);
     }
 }
 pub mod computations {
+    use std::borrow::Cow;
 
-    use crate::features::{rays::Ray, shape::Shape, tuple::Tuple};
+    use crate::features::{
+        rays::Ray,
+        shape::Shape,
+        tuple::{Point3, Vector3},
+    };
+
+    use super::Intersection;
+
+    // how far over_point and under_point sit off the surface, relative to the
+    // size of the hit's coordinates: far from the origin the round-off in the
+    // hit point grows, and a fixed offset would no longer clear it
+    pub const SHADOW_EPSILON: f64 = 0.000001;
 
-    use super::{hit, Intersection};
+    pub fn surface_offset(point: &Point3) -> f64 {
+        SHADOW_EPSILON * point.x.abs().max(point.y.abs()).max(point.z.abs()).max(1.0)
+    }
 
-    pub struct Computation {
-        pub t: f32,
-        pub object: Shape,
-        pub point: Tuple,
-        pub eyev: Tuple,
-        pub normalv: Tuple,
-        pub reflectv: Tuple,
+    pub struct Computation<'a> {
+        pub t: f64,
+        pub object: &'a Shape,
+        pub point: Point3,
+        pub eyev: Vector3,
+        pub normalv: Vector3,
+        pub reflectv: Vector3,
         pub inside: bool,
-        pub over_point: Tuple,
-        pub under_point: Tuple,
-        pub n1: f32,
-        pub n2: f32,
+        pub over_point: Point3,
+        pub under_point: Point3,
+        pub n1: f64,
+        pub n2: f64,
+        pub time: f64,
     }
 
-    impl Computation {
-        pub fn new(i: &Intersection, r: &Ray, xs: &[Intersection]) -> Self {
+    impl<'a> Computation<'a> {
+        pub fn new(i: &Intersection<'a>, r: &Ray, xs: &[Intersection<'a>]) -> Self {
             let mut n1 = 1.0;
             let mut n2 = 1.0;
-            let mut containers: Vec<Shape> = vec![];
+            // the shapes the ray is inside of, told apart by identity so two
+            // identical glass spheres are still two containers
+            let mut containers: Vec<&Shape> = vec![];
             for x in xs.iter() {
                 if *i == *x {
                     if let Some(l) = containers.last() {
                         n1 = l.material().refractive_index;
                     }
                 }
-                if let Some(index) = containers.iter().position(|a| *a == x.s) {
+                if let Some(index) = containers.iter().position(|a| x.is_on(a)) {
                     containers.remove(index);
                 } else {
-                    containers.push(x.s.clone())
+                    containers.push(x.object)
                 }
                 if *i == *x {
                     if let Some(l) = containers.last() {
@@ -214,134 +245,204 @@ pub mod computations {
                     break;
                 }
             }
-            let mut normalv = i.s.normal_at(r.position(i.t));
+            let point = r.position(i.t);
+            let surface = posed(i.object, r.time);
+            let mut normalv = surface.normal_at(point);
+            if let Some(bump) = &surface.material().bump {
+                normalv = bump.perturb(&surface, &point, &normalv);
+            }
             let mut inside = false;
             if normalv.dot(&-(r.direction)) < 0.0 {
                 inside = true;
                 normalv = -normalv;
             }
+            let offset = surface_offset(&point);
             Computation {
                 t: i.t,
-                object: i.s.clone(),
-                point: r.position(i.t),
+                object: i.object,
+                point,
                 eyev: -(r.direction),
                 normalv,
                 reflectv: r.direction.reflect(&normalv),
                 inside,
-                over_point: r.position(i.t) + (normalv * 0.0001),
-                under_point: r.position(i.t) - (normalv * 0.0001),
+                over_point: point + normalv * offset,
+                under_point: point - normalv * offset,
                 n1,
                 n2,
+                time: r.time,
             }
         }
+
+        // the share of light reflected rather than refracted at the hit, by
+        // Schlick's approximation of the Fresnel equations
+        pub fn schlick(&self) -> f64 {
+            let mut cos = self.eyev.dot(&self.normalv);
+            if self.n1 > self.n2 {
+                let n = self.n1 / self.n2;
+                let sin2_t = n * n * (1.0 - cos * cos);
+                if sin2_t > 1.0 {
+                    return 1.0;
+                }
+                cos = (1.0 - sin2_t).sqrt();
+            }
+            let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+            r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+        }
+
+        // the hit shape as it was posed at the time of the ray, for anything
+        // that looks at its transform, like patterns
+        pub fn surface(&self) -> Cow<'a, Shape> {
+            posed(self.object, self.time)
+        }
+    }
+
+    // a shape that was hit at `time` could be posed then, so the fallback is
+    // never taken in practice
+    fn posed(object: &Shape, time: f64) -> Cow<'_, Shape> {
+        object.at_time(time).unwrap_or(Cow::Borrowed(object))
     }
     #[cfg(test)]
     mod computation_tests {
-        use std::f32::EPSILON;
-
         use crate::features::{
-            intersections::Intersection, rays::Ray, shape::Shape, spheres::Sphere,
-            transformations::translation, tuple::Tuple,
+            bumps::{Bump, Ripple},
+            intersections::Intersection,
+            planes::Plane,
+            rays::Ray,
+            shape::Shape,
+            spheres::Sphere,
+            transformations::{scaling, translation},
+            tuple::{Point3, Vector3},
         };
 
-        use super::Computation;
+        use super::{Computation, SHADOW_EPSILON};
 
         #[test]
         fn test_prepare_computation() {
-            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
             let s = Shape::Sphere(Sphere::new());
-            let i = Intersection::new(4.0, s.clone());
+            let i = Intersection::new(4.0, &s);
             let comps = Computation::new(&i, &r, &[]);
             assert_eq!(comps.t, i.t);
-            assert_eq!(comps.object, i.s);
-            assert_eq!(comps.point, Tuple::point(0.0, 0.0, -1.0));
-            assert_eq!(comps.eyev, Tuple::vector(0.0, 0.0, -1.0));
-            assert_eq!(comps.normalv, Tuple::vector(0.0, 0.0, -1.0));
+            assert!(i.is_on(comps.object));
+            assert_eq!(comps.point, Point3::new(0.0, 0.0, -1.0));
+            assert_eq!(comps.eyev, Vector3::new(0.0, 0.0, -1.0));
+            assert_eq!(comps.normalv, Vector3::new(0.0, 0.0, -1.0));
         }
         #[test]
         fn test_hit_outside() {
-            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
             let s = Shape::Sphere(Sphere::new());
-            let i = Intersection::new(4.0, s.clone());
+            let i = Intersection::new(4.0, &s);
             let comps = Computation::new(&i, &r, &[]);
             assert!(!comps.inside);
         }
         #[test]
         fn test_hit_inside() {
-            let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
             let s = Shape::Sphere(Sphere::new());
-            let i = Intersection::new(1.0, s.clone());
+            let i = Intersection::new(1.0, &s);
             let comps = Computation::new(&i, &r, &[]);
-            assert_eq!(comps.point, Tuple::point(0.0, 0.0, 1.0));
-            assert_eq!(comps.eyev, Tuple::vector(0.0, 0.0, -1.0));
+            assert_eq!(comps.point, Point3::new(0.0, 0.0, 1.0));
+            assert_eq!(comps.eyev, Vector3::new(0.0, 0.0, -1.0));
             assert!(comps.inside);
-            assert_eq!(comps.normalv, Tuple::vector(0.0, 0.0, -1.0))
+            assert_eq!(comps.normalv, Vector3::new(0.0, 0.0, -1.0))
         }
         #[test]
         fn test_hit_offset_point() {
-            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
             let mut s = Shape::Sphere(Sphere::new());
-            s.set_transform(translation(0.0, 0.0, 1.0));
-            let i = Intersection::new(5.0, s.clone());
+            s.set_transform(translation(0.0, 0.0, 1.0)).unwrap();
+            let i = Intersection::new(5.0, &s);
             let comps = Computation::new(&i, &r, &[]);
-            assert!(comps.over_point.z < -EPSILON / 2.0);
+            assert!(comps.over_point.z < -SHADOW_EPSILON / 2.0);
             assert!(comps.point.z > comps.over_point.z)
         }
+        #[test]
+        fn test_offset_clears_surface_far_from_origin() {
+            let mut s = Shape::Sphere(Sphere::new());
+            s.set_transform(translation(0.0, 0.0, 1e11) * scaling(1e8, 1e8, 1e8))
+                .unwrap();
+            for ix in 0..50 {
+                let x = ix as f64 * 1.7e6;
+                let r = Ray::new(Point3::new(x, 2e7, 0.0), Vector3::new(0.0, 0.0, 1.0));
+                let xs = s.intersect(&r);
+                let comps = Computation::new(&xs[0], &r, &xs);
+                // leaving along the normal from over_point must not meet the sphere again
+                let out = s.intersect(&Ray::new(comps.over_point, comps.normalv));
+                assert!(out.iter().all(|x| x.t < 0.0));
+            }
+        }
+        #[test]
+        fn test_bump_perturbs_normal() {
+            let r = Ray::new(Point3::new(0.5, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+            let mut plane = Plane::new();
+            plane.material.bump = Some(Bump::Ripple(Ripple::new(2.0, 0.1)));
+            let plane = Shape::Plane(plane);
+            let i = Intersection::new(1.0, &plane);
+            let comps = Computation::new(&i, &r, &[]);
+            assert!(comps.normalv.x < 0.0);
+            assert!(comps.normalv.y > 0.0);
+            assert!(!comps.inside);
+        }
     }
 }
 
 #[cfg(test)]
 mod reflectv_tests {
-    use crate::features::{planes::Plane, rays::Ray, tuple::Tuple};
+    use crate::features::{
+        planes::Plane,
+        rays::Ray,
+        tuple::{Point3, Vector3},
+    };
 
     use super::{computations::Computation, *};
     #[test]
     fn test_precomputing_reflection_vector() {
         let shape = Shape::Plane(Plane::new());
         let r = Ray::new(
-            Tuple::point(0.0, 1.0, -1.0),
-            Tuple::vector(0.0, -(2.0_f32.sqrt() / 2.0), 2.0_f32.sqrt() / 2.0),
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -(2.0_f64.sqrt() / 2.0), 2.0_f64.sqrt() / 2.0),
         );
-        let i = Intersection::new(2.0_f32.sqrt(), shape);
+        let i = Intersection::new(2.0_f64.sqrt(), &shape);
         let comps = Computation::new(&i, &r, &[]);
         assert_eq!(
             comps.reflectv,
-            Tuple::vector(0.0, 2.0_f32.sqrt() / 2.0, 2.0_f32.sqrt() / 2.0)
+            Vector3::new(0.0, 2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0)
         )
     }
 }
 
 #[cfg(test)]
 mod reflection_refraction_tests {
-    use std::f32::EPSILON;
-
     use crate::features::{
         rays::Ray,
+        shape::Primitive,
         spheres::Sphere,
         transformations::{scaling, translation},
-        tuple::Tuple,
+        tuple::{Point3, Vector3},
     };
 
     use super::{computations::Computation, *};
     #[test]
     fn test_n1_n2() {
         let mut a = Sphere::glass_sphere();
-        a.transform = scaling(2.0, 2.0, 2.0);
+        a.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
         a.material.refractive_index = 1.5;
         let mut b = Sphere::glass_sphere();
-        b.transform = translation(0.0, 0.0, -0.25);
+        b.set_transform(translation(0.0, 0.0, -0.25)).unwrap();
         b.material.refractive_index = 2.0;
         let mut c = Sphere::glass_sphere();
-        c.transform = translation(0.0, 0.0, 0.25);
+        c.set_transform(translation(0.0, 0.0, 0.25)).unwrap();
         c.material.refractive_index = 2.5;
-        let r = Ray::new(Tuple::point(0.0, 0.0, -4.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -4.0), Vector3::new(0.0, 0.0, 1.0));
+        let (a, b, c) = (Shape::Sphere(a), Shape::Sphere(b), Shape::Sphere(c));
         let xs = intersections(&mut [
-            Intersection::new(2.0, Shape::Sphere(a.clone())),
-            Intersection::new(2.75, Shape::Sphere(b.clone())),
-            Intersection::new(3.25, Shape::Sphere(c.clone())),
-            Intersection::new(4.75, Shape::Sphere(b.clone())),
-            Intersection::new(5.25, Shape::Sphere(c.clone())),
-            Intersection::new(6.0, Shape::Sphere(a.clone())),
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
         ]);
         let results = [
             (1.0, 1.5),
@@ -358,15 +459,72 @@ mod reflection_refraction_tests {
         }
     }
 
+    #[test]
+    fn identical_shapes_are_separate_containers() {
+        // two overlapping glass spheres that compare equal; entering the
+        // second must not count as leaving the first
+        let mut a = Sphere::glass_sphere();
+        a.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let outer = Shape::Sphere(a.clone());
+        let inner = Shape::Sphere(a);
+        assert_eq!(outer, inner);
+        let r = Ray::new(Point3::new(0.0, 0.0, -4.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = intersections(&mut [
+            Intersection::new(2.0, &outer),
+            Intersection::new(2.5, &inner),
+            Intersection::new(5.5, &inner),
+            Intersection::new(6.0, &outer),
+        ]);
+        let comps = Computation::new(&xs[1], &r, &xs);
+        assert_eq!((comps.n1, comps.n2), (1.5, 1.5));
+        let comps = Computation::new(&xs[2], &r, &xs);
+        assert_eq!((comps.n1, comps.n2), (1.5, 1.5));
+        let comps = Computation::new(&xs[3], &r, &xs);
+        assert_eq!((comps.n1, comps.n2), (1.5, 1.0));
+    }
+
     #[test]
     fn test_under_point() {
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let mut shape = Sphere::glass_sphere();
-        shape.transform = translation(0.0, 0.0, 1.0);
-        let i = Intersection::new(5.0, Shape::Sphere(shape));
-        let xs = intersections(&mut [i.clone()]);
+        shape.set_transform(translation(0.0, 0.0, 1.0)).unwrap();
+        let shape = Shape::Sphere(shape);
+        let i = Intersection::new(5.0, &shape);
+        let xs = intersections(&mut [i]);
         let comps = Computation::new(&i, &r, &xs);
-        assert!(comps.under_point.z > 0.0001 / 2.0);
+        assert!(comps.under_point.z > computations::SHADOW_EPSILON / 2.0);
         assert!(comps.point.z < comps.under_point.z);
     }
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let shape = Shape::Sphere(Sphere::glass_sphere());
+        let r = Ray::new(
+            Point3::new(0.0, 0.0, 2_f64.sqrt() / 2.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let xs = intersections(&mut [
+            Intersection::new(-(2_f64.sqrt() / 2.0), &shape),
+            Intersection::new(2_f64.sqrt() / 2.0, &shape),
+        ]);
+        let comps = Computation::new(&xs[1], &r, &xs);
+        assert_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn schlick_at_perpendicular_and_grazing_angles() {
+        let shape = Shape::Sphere(Sphere::glass_sphere());
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let xs = intersections(&mut [
+            Intersection::new(-1.0, &shape),
+            Intersection::new(1.0, &shape),
+        ]);
+        let comps = Computation::new(&xs[1], &r, &xs);
+        assert!((comps.schlick() - 0.04).abs() < 0.00001);
+
+        let r = Ray::new(Point3::new(0.0, 0.99, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = intersections(&mut [Intersection::new(1.8589, &shape)]);
+        let comps = Computation::new(&xs[0], &r, &xs);
+        assert!((comps.schlick() - 0.48873).abs() < 0.00001);
+    }
 }
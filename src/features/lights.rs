@@ -1,13 +1,13 @@
-use super::tuple::Tuple;
+use super::tuple::{Color, Point3, Vector3};
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Light {
-    pub position: Tuple,
-    pub intensity: Tuple,
+    pub position: Point3,
+    pub intensity: Color,
 }
 
 impl Light {
-    pub fn new(position: Tuple, intensity: Tuple) -> Self {
+    pub fn new(position: Point3, intensity: Color) -> Self {
         Light {
             position,
             intensity,
@@ -15,16 +15,33 @@ impl Light {
     }
 }
 
+// Light from so far away that it arrives along one direction everywhere,
+// like sunlight; `direction` points from the scene toward the light
+#[derive(PartialEq, Debug, Clone)]
+pub struct Sun {
+    pub direction: Vector3,
+    pub intensity: Color,
+}
+
+impl Sun {
+    pub fn new(direction: Vector3, intensity: Color) -> Self {
+        Sun {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+}
+
 #[cfg(test)]
 mod light_tests {
-    use crate::features::tuple::Tuple;
+    use crate::features::tuple::{Color, Point3};
 
     use super::Light;
 
     #[test]
     fn test_creating_light() {
-        let intensity = Tuple::color(1.0, 1.0, 1.0);
-        let position = Tuple::point(0.0, 0.0, 0.0);
+        let intensity = Color::new(1.0, 1.0, 1.0);
+        let position = Point3::new(0.0, 0.0, 0.0);
         let light = Light::new(position, intensity);
         assert_eq!(light.intensity, intensity);
         assert_eq!(light.position, position);
@@ -1,12 +1,14 @@
-use rand::prelude::*;
 use std::ops::Mul;
 
-use super::tuple::{Tuple, TupleType};
+use super::{
+    simd::Simd,
+    tuple::{Point3, Tuple, TupleType, Vector3},
+};
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Matrice {
     pub size: usize,
-    pub matrice: Vec<Vec<f32>>,
+    pub matrice: Vec<Vec<f64>>,
 }
 
 impl Eq for Matrice {}
@@ -20,7 +22,9 @@ impl Matrice {
         }
     }
 
+    #[cfg(test)]
     fn random(size: usize) -> Self {
+        use rand::Rng;
         let mut rng = rand::thread_rng();
         let mut out = Self::new(size);
         for ix in 0..size {
@@ -46,7 +50,7 @@ impl Matrice {
         }
         Some(out)
     }
-    pub fn determinant(&self) -> f32 {
+    pub fn determinant(&self) -> f64 {
         if self.size == 2 {
             return self.matrice[0][0] * self.matrice[1][1]
                 - self.matrice[0][1] * self.matrice[1][0];
@@ -82,10 +86,10 @@ impl Matrice {
     pub fn size(&self) -> usize {
         self.size
     }
-    pub fn element_at(&self, row: usize, column: usize) -> f32 {
+    pub fn element_at(&self, row: usize, column: usize) -> f64 {
         self.matrice[row][column]
     }
-    pub fn write_element(&mut self, row: usize, column: usize, element: f32) {
+    pub fn write_element(&mut self, row: usize, column: usize, element: f64) {
         self.matrice[row][column] = element;
     }
 
@@ -109,12 +113,12 @@ impl Matrice {
         out
     }
 
-    pub fn minor(&self, row: usize, column: usize) -> f32 {
+    pub fn minor(&self, row: usize, column: usize) -> f64 {
         self.submatrix(row, column).determinant()
     }
 
-    pub fn cofactor(&self, row: usize, column: usize) -> f32 {
-        if (row + column) % 2 != 0 {
+    pub fn cofactor(&self, row: usize, column: usize) -> f64 {
+        if !(row + column).is_multiple_of(2) {
             return -self.minor(row, column);
         }
         self.minor(row, column)
@@ -143,11 +147,11 @@ impl Mul<Tuple> for Matrice {
     type Output = Tuple;
 
     fn mul(self, rhs: Tuple) -> Self::Output {
-        let mut tuple = vec![rhs.x, rhs.y, rhs.z, 0.0];
+        let mut tuple = [rhs.x, rhs.y, rhs.z, 0.0];
         if rhs.w == TupleType::Point {
             tuple[3] = 1.0;
         }
-        let mut out = vec![0.0; 4];
+        let mut out = [0.0; 4];
         for (ix, row) in self.matrice.iter().enumerate() {
             for (jx, col) in row.iter().enumerate() {
                 out[ix] += *col * tuple[jx];
@@ -157,6 +161,307 @@ impl Mul<Tuple> for Matrice {
     }
 }
 
+// The 4x4 matrix the transform pipeline runs on. It lives on the stack and
+// is Copy, so products and inverses never allocate. `Matrice` stays around
+// for the general n by n operations (submatrices, minors, cofactors).
+#[derive(Clone, Copy, Debug)]
+pub struct Matrice4 {
+    m: [[f64; 4]; 4],
+}
+
+const EPSILON: f64 = 0.00001;
+
+impl PartialEq for Matrice4 {
+    fn eq(&self, other: &Self) -> bool {
+        self.m
+            .iter()
+            .flatten()
+            .zip(other.m.iter().flatten())
+            .all(|(a, b)| (a - b).abs() < EPSILON)
+    }
+}
+
+impl Eq for Matrice4 {}
+
+impl Default for Matrice4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Matrice4 {
+    pub const IDENTITY: Matrice4 = Matrice4 {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Self { m: rows }
+    }
+    pub fn identity() -> Self {
+        Self::IDENTITY
+    }
+    pub fn rows(&self) -> [[f64; 4]; 4] {
+        self.m
+    }
+    pub fn is_finite(&self) -> bool {
+        self.m.iter().flatten().all(|x| x.is_finite())
+    }
+    pub fn element_at(&self, row: usize, column: usize) -> f64 {
+        self.m[row][column]
+    }
+    pub fn write_element(&mut self, row: usize, column: usize, element: f64) {
+        self.m[row][column] = element;
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::new(std::array::from_fn(|row| {
+            std::array::from_fn(|col| self.m[col][row])
+        }))
+    }
+
+    pub fn determinant(&self) -> f64 {
+        // Gaussian elimination: the determinant is the product of the pivots,
+        // with a sign flip for every row swap
+        let mut m = self.m;
+        let mut det = 1.0;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|a, b| m[*a][col].abs().total_cmp(&m[*b][col].abs()))
+                .unwrap();
+            if m[pivot][col] == 0.0 {
+                return 0.0;
+            }
+            if pivot != col {
+                m.swap(pivot, col);
+                det = -det;
+            }
+            det *= m[col][col];
+            let (upper, lower) = m.split_at_mut(col + 1);
+            let pivot_row = &upper[col];
+            for row in lower {
+                let factor = row[col] / pivot_row[col];
+                for (x, p) in row.iter_mut().zip(pivot_row).skip(col) {
+                    *x -= factor * p;
+                }
+            }
+        }
+        det
+    }
+
+    // Gauss-Jordan elimination with scaled partial pivoting; None when
+    // singular. Pivots are measured against the largest entry of their row,
+    // so a tiny but uniform scale still inverts.
+    pub fn inverse(&self) -> Option<Self> {
+        if !self.is_finite() {
+            return None;
+        }
+        let mut m = self.m;
+        let mut inv = Self::IDENTITY.m;
+        let mut row_scale = m.map(|row| row.iter().fold(0.0_f64, |a, x| a.max(x.abs())));
+        if row_scale.contains(&0.0) {
+            return None;
+        }
+        for col in 0..4 {
+            let relative = |row: usize| m[row][col].abs() / row_scale[row];
+            let pivot = (col..4)
+                .max_by(|a, b| relative(*a).total_cmp(&relative(*b)))
+                .unwrap();
+            // below this the pivot is round-off from a dependent row
+            if relative(pivot) < 1e-10 {
+                return None;
+            }
+            m.swap(pivot, col);
+            inv.swap(pivot, col);
+            row_scale.swap(pivot, col);
+            let scale = 1.0 / m[col][col];
+            for k in 0..4 {
+                m[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = m[row][col];
+                for k in 0..4 {
+                    m[row][k] -= factor * m[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
+            }
+        }
+        Some(Self::new(inv))
+    }
+}
+
+impl Mul for Matrice4 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Matrice4::new(std::array::from_fn(|row| {
+            std::array::from_fn(|col| (0..4).map(|k| self.m[row][k] * rhs.m[k][col]).sum())
+        }))
+    }
+}
+
+impl Mul<&Matrice4> for &Matrice4 {
+    type Output = Matrice4;
+    fn mul(self, rhs: &Matrice4) -> Matrice4 {
+        *self * *rhs
+    }
+}
+
+impl Matrice4 {
+    // each output component is one row times the homogeneous tuple, a SIMD
+    // multiply followed by a horizontal sum
+    fn apply(&self, lanes: Simd<4>) -> Simd<4> {
+        Simd::from_fn(|r| (Simd(self.m[r]) * lanes).sum())
+    }
+}
+
+// points are transformed with w = 1 and so pick up translation
+impl Mul<Point3> for &Matrice4 {
+    type Output = Point3;
+    fn mul(self, rhs: Point3) -> Point3 {
+        let mut lanes = rhs.lanes();
+        lanes.0[3] = 1.0;
+        Point3::from_lanes(self.apply(lanes))
+    }
+}
+
+impl Mul<Point3> for Matrice4 {
+    type Output = Point3;
+    fn mul(self, rhs: Point3) -> Point3 {
+        &self * rhs
+    }
+}
+
+// vectors are transformed with w = 0, translation leaves them alone
+impl Mul<Vector3> for &Matrice4 {
+    type Output = Vector3;
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        Vector3::from_lanes(self.apply(rhs.lanes()))
+    }
+}
+
+impl Mul<Vector3> for Matrice4 {
+    type Output = Vector3;
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        &self * rhs
+    }
+}
+
+impl From<Matrice> for Matrice4 {
+    fn from(matrice: Matrice) -> Self {
+        assert_eq!(matrice.size, 4, "only a 4x4 matrice converts to Matrice4");
+        Self::new(std::array::from_fn(|row| {
+            std::array::from_fn(|col| matrice.element_at(row, col))
+        }))
+    }
+}
+
+impl From<Matrice4> for Matrice {
+    fn from(matrice: Matrice4) -> Self {
+        let mut out = Matrice::new(4);
+        for row in 0..4 {
+            for col in 0..4 {
+                out.write_element(row, col, matrice.element_at(row, col));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod matrice4_tests {
+    use crate::features::transformations::scaling;
+
+    use super::*;
+
+    fn sample() -> Matrice4 {
+        Matrice4::new([
+            [-5.0, 2.0, 6.0, -8.0],
+            [1.0, -5.0, 1.0, 8.0],
+            [7.0, 7.0, -6.0, -7.0],
+            [1.0, -3.0, 7.0, 4.0],
+        ])
+    }
+
+    #[test]
+    fn determinant_matches_cofactor_expansion() {
+        let m = sample();
+        assert!((m.determinant() - 532.0).abs() < 1e-9);
+        let general: Matrice = m.into();
+        assert_eq!(general.determinant(), 532.0);
+    }
+
+    #[test]
+    fn gauss_jordan_inverse_matches_cofactor_inverse() {
+        let m = sample();
+        let general: Matrice = m.into();
+        let expected: Matrice4 = general.inverse().unwrap().into();
+        assert_eq!(m.inverse().unwrap(), expected);
+        let inverse = m.inverse().unwrap();
+        let (a, b) = (&m, &inverse);
+        assert_eq!(a * b, Matrice4::identity());
+    }
+
+    #[test]
+    fn inverse_of_singular_matrix_is_none() {
+        let mut m = sample();
+        for col in 0..4 {
+            m.write_element(3, col, m.element_at(0, col) * 2.0);
+        }
+        assert!(m.determinant().abs() < 1e-9);
+        assert!(m.inverse().is_none());
+    }
+
+    #[test]
+    fn small_uniform_scales_invert() {
+        for s in [1e-4, 1e-12] {
+            let m = scaling(s, s, s);
+            let inverse = m.inverse().unwrap();
+            assert_eq!(inverse, scaling(1.0 / s, 1.0 / s, 1.0 / s));
+            assert_eq!(m * inverse, Matrice4::identity());
+        }
+        // a large matrix with dependent rows is still singular
+        let mut m = Matrice4::identity();
+        for col in 0..4 {
+            m.write_element(0, col, 1e12 * (col + 1) as f64);
+            m.write_element(1, col, 3e12 * (col + 1) as f64);
+        }
+        assert!(m.inverse().is_none());
+    }
+
+    #[test]
+    fn product_and_transpose() {
+        let a = sample();
+        let b = a.transpose();
+        assert_eq!(b.element_at(0, 1), 1.0);
+        assert_eq!(a * Matrice4::identity(), a);
+        let product: Matrice = (a * b).into();
+        let expected = Matrice::from(a) * Matrice::from(b);
+        assert_eq!(product, expected);
+    }
+
+    #[test]
+    fn multiplying_points_and_vectors_by_reference() {
+        let m = Matrice4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 4.0, 2.0],
+            [8.0, 6.0, 4.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let p = Point3::new(1.0, 2.0, 3.0);
+        let by_ref = &m;
+        assert_eq!(by_ref * p, Point3::new(18.0, 24.0, 33.0));
+        assert_eq!(m * Vector3::new(1.0, 0.0, 0.0), Vector3::new(1.0, 2.0, 8.0));
+    }
+}
+
 #[cfg(test)]
 mod matrice_tests {
     use std::vec;
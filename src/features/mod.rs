@@ -1,15 +1,31 @@
+pub mod animation;
+pub mod background;
+pub mod bumps;
 pub mod camera;
 pub mod canvas;
 pub mod cube;
+pub mod environment;
+pub mod error;
 pub mod intersections;
 pub mod lights;
 pub mod materials;
 pub mod matrice;
+pub mod motion;
+pub mod noise;
+pub mod occlusion;
+pub mod packets;
+pub mod path_tracer;
 pub mod patterns;
+pub mod pbr;
 pub mod planes;
+pub mod quads;
 pub mod rays;
+pub mod sampling;
 pub mod shape;
+pub mod simd;
+pub mod sky;
 pub mod spheres;
+pub mod stereo;
 pub mod transformations;
 pub mod tuple;
 pub mod world;
@@ -0,0 +1,82 @@
+use super::matrice::Matrice4;
+
+// A transform that changes over the shutter interval, given as keyframes
+// sorted by time. In between keys the matrices are blended element by
+// element, which is exact for translation and scaling; large rotations
+// should be split over several keys.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Motion {
+    keys: Vec<(f64, Matrice4)>,
+}
+
+impl Eq for Motion {}
+
+impl Motion {
+    // moves from `start` at time 0 to `end` at time 1
+    pub fn linear(start: Matrice4, end: Matrice4) -> Self {
+        Self::keyframes(vec![(0.0, start), (1.0, end)])
+    }
+
+    pub fn keyframes(mut keys: Vec<(f64, Matrice4)>) -> Self {
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { keys }
+    }
+
+    pub fn at(&self, time: f64) -> Matrice4 {
+        let Some(first) = self.keys.first() else {
+            return Matrice4::identity();
+        };
+        if time <= first.0 {
+            return first.1;
+        }
+        for pair in self.keys.windows(2) {
+            let ((t0, m0), (t1, m1)) = (&pair[0], &pair[1]);
+            if time <= *t1 {
+                let fraction = (time - t0) / (t1 - t0);
+                let (a, b) = (m0.rows(), m1.rows());
+                return Matrice4::new(std::array::from_fn(|row| {
+                    std::array::from_fn(|col| a[row][col] + (b[row][col] - a[row][col]) * fraction)
+                }));
+            }
+        }
+        self.keys[self.keys.len() - 1].1
+    }
+}
+
+#[cfg(test)]
+mod motion_tests {
+    use crate::features::{
+        transformations::{scaling, translation},
+        tuple::Point3,
+    };
+
+    use super::*;
+
+    #[test]
+    fn linear_motion_interpolates_translation() {
+        let m = Motion::linear(translation(0.0, 0.0, 0.0), translation(2.0, 4.0, 0.0));
+        assert_eq!(m.at(0.0), translation(0.0, 0.0, 0.0));
+        assert_eq!(m.at(0.25), translation(0.5, 1.0, 0.0));
+        assert_eq!(m.at(1.0), translation(2.0, 4.0, 0.0));
+    }
+
+    #[test]
+    fn motion_clamps_outside_key_range() {
+        let m = Motion::linear(scaling(1.0, 1.0, 1.0), scaling(3.0, 3.0, 3.0));
+        assert_eq!(m.at(-1.0), scaling(1.0, 1.0, 1.0));
+        assert_eq!(m.at(2.0), scaling(3.0, 3.0, 3.0));
+    }
+
+    #[test]
+    fn keyframes_are_sorted_and_followed() {
+        let m = Motion::keyframes(vec![
+            (1.0, translation(0.0, 0.0, 0.0)),
+            (0.0, translation(-1.0, 0.0, 0.0)),
+            (0.5, translation(1.0, 0.0, 0.0)),
+        ]);
+        let p = Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(m.at(0.25) * p, Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(m.at(0.5) * p, Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(m.at(0.75) * p, Point3::new(0.5, 0.0, 0.0));
+    }
+}
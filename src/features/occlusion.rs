@@ -0,0 +1,134 @@
+use indicatif::ProgressBar;
+
+use super::{
+    camera::cameras::Camera, canvas::Canvas, intersections::computations::Computation, rays::Ray,
+    sampling::cosine_hemisphere, tuple::Color, world::World,
+};
+
+// Ambient occlusion: how much of the sky above a point is left open by the
+// geometry around it. Rays are cast over the hemisphere on an n by n grid
+// and anything hit within `distance` counts as blocking, so creases and
+// contact points darken while open surfaces are unaffected.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AmbientOcclusion {
+    pub samples: usize,
+    pub distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: usize, distance: f64) -> Self {
+        Self { samples, distance }
+    }
+
+    // the cosine-weighted fraction of the hemisphere that is open, from 0
+    // in a closed pocket to 1 on an open plane
+    pub fn visibility(&self, world: &World, comps: &Computation) -> f64 {
+        let n = self.samples.max(1);
+        let mut open = 0;
+        for ix in 0..n {
+            for jx in 0..n {
+                let (u1, u2) = ((ix as f64 + 0.5) / n as f64, (jx as f64 + 0.5) / n as f64);
+                let direction = cosine_hemisphere(&comps.normalv, u1, u2);
+                let r = Ray::with_time(comps.over_point, direction, comps.time);
+                if !world.any_hit(&r, 0.0, self.distance) {
+                    open += 1;
+                }
+            }
+        }
+        open as f64 / (n * n) as f64
+    }
+
+    // an image of the visibility alone, white where nothing is hit
+    pub fn render(&self, camera: &Camera, world: &World) -> Canvas {
+        let width = camera.hsize as usize;
+        let height = camera.vsize as usize;
+        let mut image = Canvas::new(width, height);
+        let bar = ProgressBar::new(height as u64);
+        for y in 0..height {
+            for x in 0..width {
+                let r = camera.ray_for_pixel(x as f64, y as f64);
+                let visibility = match world.closest_hit(&r, 0.0, f64::INFINITY) {
+                    Some(i) => self.visibility(world, &Computation::new(&i, &r, &[])),
+                    None => 1.0,
+                };
+                image.canvas[y][x] = Color::new(visibility, visibility, visibility);
+            }
+            bar.inc(1);
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod occlusion_tests {
+    use std::f64::consts::PI;
+
+    use crate::features::{
+        intersections::Intersection,
+        lights::Light,
+        planes::Plane,
+        shape::{Primitive, Shape},
+        spheres::Sphere,
+        transformations::{translation, view_transformation},
+        tuple::{Point3, Vector3},
+    };
+
+    use super::*;
+
+    fn floor_with_ball() -> World {
+        let mut ball = Sphere::new();
+        ball.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        World::new(
+            Light::new(Point3::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)),
+            &[Shape::Plane(Plane::new()), Shape::Sphere(ball)],
+        )
+    }
+
+    fn floor_visibility(world: &World, ao: &AmbientOcclusion, x: f64) -> f64 {
+        let r = Ray::new(Point3::new(x, 1.0, -1.0), Vector3::new(0.0, -1.0, 0.0));
+        let i = Intersection::new(1.0, &world.shapes()[0]);
+        ao.visibility(world, &Computation::new(&i, &r, &[]))
+    }
+
+    #[test]
+    fn open_floor_is_fully_visible() {
+        let world = floor_with_ball();
+        let ao = AmbientOcclusion::new(8, 1.0);
+        assert_eq!(floor_visibility(&world, &ao, 10.0), 1.0);
+    }
+
+    #[test]
+    fn floor_next_to_a_ball_is_darker_up_to_the_distance() {
+        let world = floor_with_ball();
+        let near = floor_visibility(&world, &AmbientOcclusion::new(8, 2.0), 0.0);
+        let further = floor_visibility(&world, &AmbientOcclusion::new(8, 2.0), 1.2);
+        assert!(near < further && further < 1.0);
+        // the ball is a unit above the floor; nothing within half of that
+        let short = floor_visibility(&world, &AmbientOcclusion::new(8, 0.5), 1.2);
+        assert_eq!(short, 1.0);
+    }
+
+    #[test]
+    fn occlusion_pass_is_white_on_misses() {
+        let world = floor_with_ball();
+        let mut camera = Camera::new(11.0, 11.0, PI / 2.0).unwrap();
+        camera
+            .set_transform(view_transformation(
+                Point3::new(0.0, 1.0, -5.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        let image = AmbientOcclusion::new(4, 1.0).render(&camera, &world);
+        // the top row looks at open sky
+        assert_eq!(image.pixel_at(5, 0).unwrap(), Color::new(1.0, 1.0, 1.0));
+        // and the floor around the ball is in its shade
+        let darkest = image
+            .canvas
+            .iter()
+            .flatten()
+            .map(|c| c.r)
+            .fold(1.0, f64::min);
+        assert!(darkest < 1.0);
+    }
+}
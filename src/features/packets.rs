@@ -0,0 +1,332 @@
+use super::{
+    intersections::Intersection,
+    matrice::Matrice4,
+    rays::Ray,
+    shape::Shape,
+    simd::{F64x4, Simd},
+    tuple::{Point3, Vector3, EPSILON},
+    world::World,
+};
+
+// x, y and z of N points or vectors, one SIMD register per axis
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Packed3<const N: usize> {
+    pub x: Simd<N>,
+    pub y: Simd<N>,
+    pub z: Simd<N>,
+}
+
+impl<const N: usize> Packed3<N> {
+    // transposes N registers of x, y, z into one register per axis
+    fn from_lanes(lanes: [F64x4; N]) -> Self {
+        Self {
+            x: Simd::from_fn(|i| lanes[i][0]),
+            y: Simd::from_fn(|i| lanes[i][1]),
+            z: Simd::from_fn(|i| lanes[i][2]),
+        }
+    }
+
+    pub fn dot(&self, other: &Self) -> Simd<N> {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    // `w` is 1 for points and 0 for vectors, as in `Matrice4 * Point3` and
+    // `Matrice4 * Vector3`
+    fn transform(&self, m: &Matrice4, w: f64) -> Self {
+        let row = |r: usize| {
+            let e = |c: usize| Simd::splat(m.element_at(r, c));
+            e(0) * self.x + e(1) * self.y + e(2) * self.z + e(3) * Simd::splat(w)
+        };
+        Self {
+            x: row(0),
+            y: row(1),
+            z: row(2),
+        }
+    }
+}
+
+// N rays traced together, laid out as a structure of arrays so each step of
+// an intersection test runs on all of them at once. Works best for coherent
+// rays, like neighbouring camera rays.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RayPacket<const N: usize> {
+    pub origin: Packed3<N>,
+    pub direction: Packed3<N>,
+    pub time: [f64; N],
+}
+
+impl<const N: usize> RayPacket<N> {
+    pub fn from_rays(rays: &[Ray; N]) -> Self {
+        Self {
+            origin: Packed3::from_lanes(std::array::from_fn(|i| rays[i].origin.lanes())),
+            direction: Packed3::from_lanes(std::array::from_fn(|i| rays[i].direction.lanes())),
+            time: std::array::from_fn(|i| rays[i].time),
+        }
+    }
+
+    pub fn ray(&self, lane: usize) -> Ray {
+        let o = &self.origin;
+        let d = &self.direction;
+        Ray::with_time(
+            Point3::new(o.x[lane], o.y[lane], o.z[lane]),
+            Vector3::new(d.x[lane], d.y[lane], d.z[lane]),
+            self.time[lane],
+        )
+    }
+
+    pub fn transform(&self, m: &Matrice4) -> Self {
+        Self {
+            origin: self.origin.transform(m, 1.0),
+            direction: self.direction.transform(m, 0.0),
+            time: self.time,
+        }
+    }
+}
+
+// Up to two intersections per lane, t0 <= t1. Lanes with a single
+// intersection (planes, quads) repeat it in t1.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PacketHits<const N: usize> {
+    pub t0: Simd<N>,
+    pub t1: Simd<N>,
+    pub count: [usize; N],
+}
+
+impl<const N: usize> PacketHits<N> {
+    fn none() -> Self {
+        Self {
+            t0: Simd::splat(f64::INFINITY),
+            t1: Simd::splat(f64::INFINITY),
+            count: [0; N],
+        }
+    }
+
+    // the same list `Shape::intersect` gives for the lane's ray
+    pub fn intersections<'a>(&self, lane: usize, shape: &'a Shape) -> Vec<Intersection<'a>> {
+        let ts = [self.t0[lane], self.t1[lane]];
+        ts[..self.count[lane]]
+            .iter()
+            .map(|t| Intersection::new(*t, shape))
+            .collect()
+    }
+
+    // smallest positive t of the lane, as `hit` picks it
+    fn nearest(&self) -> Simd<N> {
+        let inf = Simd::splat(f64::INFINITY);
+        let hit = |t: Simd<N>, n: usize| {
+            let valid: [bool; N] = std::array::from_fn(|i| self.count[i] >= n && t[i] > 0.0);
+            Simd::select(valid, t, inf)
+        };
+        hit(self.t0, 1).min(hit(self.t1, 2))
+    }
+}
+
+impl Shape {
+    // `intersect` for N rays at once
+    pub fn intersect_packet<const N: usize>(&self, packet: &RayPacket<N>) -> PacketHits<N> {
+        // every lane of a moving shape sees its own pose, so go one by one
+        if self.motion().is_some() {
+            return self.intersect_lanes(packet);
+        }
+        let local = packet.transform(self.inverse());
+        match self {
+            Shape::Sphere(_) => sphere_packet(&local),
+            Shape::Plane(_) => plane_packet(&local, false),
+            Shape::Quad(_) => plane_packet(&local, true),
+            Shape::Cube(_) => cube_packet(&local),
+            Shape::Custom(_) => self.intersect_lanes(packet),
+        }
+    }
+
+    // the scalar path for each lane, keeping the first two intersections
+    fn intersect_lanes<const N: usize>(&self, packet: &RayPacket<N>) -> PacketHits<N> {
+        let mut hits = PacketHits::none();
+        for lane in 0..N {
+            let xs = self.intersect(&packet.ray(lane));
+            hits.count[lane] = xs.len().min(2);
+            if let Some(first) = xs.first() {
+                hits.t0.0[lane] = first.t;
+                hits.t1.0[lane] = xs.get(1).unwrap_or(first).t;
+            }
+        }
+        hits
+    }
+}
+
+fn sphere_packet<const N: usize>(r: &RayPacket<N>) -> PacketHits<N> {
+    let two = Simd::splat(2.0);
+    let a = r.direction.dot(&r.direction);
+    let b = two * r.direction.dot(&r.origin);
+    let c = r.origin.dot(&r.origin) - Simd::splat(1.0);
+    let discriminant = b * b - Simd::splat(4.0) * a * c;
+    let hit = Simd::splat(0.0).le(discriminant);
+    let root = discriminant.max(Simd::splat(0.0)).sqrt();
+    PacketHits {
+        t0: (-b - root) / (two * a),
+        t1: (-b + root) / (two * a),
+        count: hit.map(|h| if h { 2 } else { 0 }),
+    }
+}
+
+// the y = 0 plane, clipped to |x|, |z| <= 1 for quads
+fn plane_packet<const N: usize>(r: &RayPacket<N>, bounded: bool) -> PacketHits<N> {
+    let t = -r.origin.y / r.direction.y;
+    let parallel = r.direction.y.abs().lt(Simd::splat(EPSILON));
+    let x = (r.origin.x + r.direction.x * t).abs();
+    let z = (r.origin.z + r.direction.z * t).abs();
+    let count = std::array::from_fn(|i| {
+        let outside = bounded && (x[i] > 1.0 || z[i] > 1.0);
+        if parallel[i] || outside {
+            0
+        } else {
+            1
+        }
+    });
+    PacketHits {
+        t0: t,
+        t1: t,
+        count,
+    }
+}
+
+fn cube_packet<const N: usize>(r: &RayPacket<N>) -> PacketHits<N> {
+    let axis = |origin: Simd<N>, direction: Simd<N>| {
+        let tmin_numerator = Simd::splat(-1.0) - origin;
+        let tmax_numerator = Simd::splat(1.0) - origin;
+        let steep = Simd::splat(EPSILON).le(direction.abs());
+        let inf = Simd::splat(f64::INFINITY);
+        let tmin = Simd::select(steep, tmin_numerator / direction, tmin_numerator * inf);
+        let tmax = Simd::select(steep, tmax_numerator / direction, tmax_numerator * inf);
+        (tmin.min(tmax), tmin.max(tmax))
+    };
+    let (xmin, xmax) = axis(r.origin.x, r.direction.x);
+    let (ymin, ymax) = axis(r.origin.y, r.direction.y);
+    let (zmin, zmax) = axis(r.origin.z, r.direction.z);
+    let tmin = xmin.max(ymin).max(zmin);
+    let tmax = xmax.min(ymax).min(zmax);
+    PacketHits {
+        count: tmin.le(tmax).map(|h| if h { 2 } else { 0 }),
+        t0: tmin,
+        t1: tmax,
+    }
+}
+
+// the closest hit of every lane over all shapes of the world, the packet
+// counterpart of `hit(intersect_world(..))`
+pub fn hit_world_packet<'a, const N: usize>(
+    world: &'a World,
+    packet: &RayPacket<N>,
+) -> [Option<Intersection<'a>>; N] {
+    let mut best = Simd::splat(f64::INFINITY);
+    let mut owner = [usize::MAX; N];
+    for (ix, shape) in world.shapes().iter().enumerate() {
+        let nearest = shape.intersect_packet(packet).nearest();
+        let closer = nearest.lt(best);
+        best = best.min(nearest);
+        for lane in 0..N {
+            if closer[lane] {
+                owner[lane] = ix;
+            }
+        }
+    }
+    std::array::from_fn(|lane| {
+        let shape = world.shapes().get(owner[lane])?;
+        Some(Intersection::new(best[lane], shape))
+    })
+}
+
+#[cfg(test)]
+mod packet_tests {
+    use std::f64::consts::PI;
+
+    use crate::features::{
+        camera::cameras::Camera,
+        cube::Cube,
+        intersections::hit,
+        lights::Light,
+        motion::Motion,
+        planes::Plane,
+        quads::Quad,
+        spheres::Sphere,
+        transformations::{rotation_y, scaling, translation, view_transformation},
+        tuple::Color,
+        world::intersect_world,
+    };
+
+    use super::*;
+
+    fn scene() -> (World, Camera) {
+        let mut sphere = Shape::Sphere(Sphere::new());
+        sphere.set_transform(translation(-1.0, 1.0, 0.0)).unwrap();
+        let mut cube = Shape::Cube(Cube::new());
+        cube.set_transform(translation(1.5, 0.5, 1.0) * rotation_y(0.6) * scaling(0.5, 0.5, 0.5))
+            .unwrap();
+        let mut quad = Shape::Quad(Quad::new());
+        quad.set_transform(translation(0.0, 2.5, 2.0) * scaling(3.0, 1.0, 1.0))
+            .unwrap();
+        let mut moving = Shape::Sphere(Sphere::new());
+        moving.set_motion(Some(Motion::linear(
+            translation(0.0, 0.3, -1.0) * scaling(0.3, 0.3, 0.3),
+            translation(0.5, 0.3, -1.0) * scaling(0.3, 0.3, 0.3),
+        )));
+        let shapes = [Shape::Plane(Plane::new()), sphere, cube, quad, moving];
+        let world = World::new(
+            Light::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)),
+            &shapes,
+        );
+        let mut camera = Camera::new(64.0, 32.0, PI / 3.0).unwrap();
+        camera
+            .set_transform(view_transformation(
+                Point3::new(0.0, 1.5, -6.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        (world, camera)
+    }
+
+    fn camera_packet<const N: usize>(camera: &Camera, x: usize, y: usize) -> RayPacket<N> {
+        let rays = std::array::from_fn(|i| camera.ray_for_pixel((x + i) as f64, y as f64));
+        RayPacket::from_rays(&rays)
+    }
+
+    #[test]
+    fn packet_intersections_match_scalar() {
+        let (world, camera) = scene();
+        for y in 0..32 {
+            for x in (0..64).step_by(4) {
+                let packet = camera_packet::<4>(&camera, x, y);
+                for shape in world.shapes() {
+                    let hits = shape.intersect_packet(&packet);
+                    for lane in 0..4 {
+                        let scalar = shape.intersect(&packet.ray(lane));
+                        let packed = hits.intersections(lane, shape);
+                        assert_eq!(scalar.len(), packed.len());
+                        for (a, b) in scalar.iter().zip(&packed) {
+                            assert!((a.t - b.t).abs() < EPSILON);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn packet_world_hits_match_scalar() {
+        let (world, camera) = scene();
+        for y in 0..32 {
+            for x in (0..64).step_by(8) {
+                let packet = camera_packet::<8>(&camera, x, y);
+                let hits = hit_world_packet(&world, &packet);
+                for (lane, packed) in hits.iter().enumerate() {
+                    let scalar = hit(intersect_world(&world, &packet.ray(lane)));
+                    assert_eq!(scalar.is_some(), packed.is_some());
+                    if let (Some(a), Some(b)) = (scalar, packed) {
+                        assert!((a.t - b.t).abs() < EPSILON);
+                        assert!(a.is_on(b.object));
+                    }
+                }
+            }
+        }
+    }
+}
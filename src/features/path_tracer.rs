@@ -0,0 +1,414 @@
+use indicatif::ProgressBar;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    camera::cameras::Camera,
+    canvas::Canvas,
+    intersections::computations::Computation,
+    materials::{direct_lightning, ShadingModel},
+    rays::Ray,
+    sampling::{cosine_hemisphere, fold_above, glossy_lobe, splitmix64},
+    tuple::{Color, Vector3},
+    world::{intersect_world, World},
+};
+
+// bounces after which paths start being terminated by russian roulette
+const ROULETTE_DEPTH: u32 = 3;
+
+// Unbiased Monte Carlo integrator, an alternative to `World::color_at`.
+// The point light and emissive shapes are sampled directly at every diffuse
+// vertex and indirect light is gathered with cosine-weighted bounces; light
+// units match the Whitted renderer, so the point light has no falloff.
+pub struct PathTracer {
+    pub samples: usize,
+    pub max_depth: u32,
+    pub seed: u64,
+}
+
+impl PathTracer {
+    pub fn new(samples: usize) -> Self {
+        Self {
+            samples,
+            max_depth: 16,
+            seed: 0,
+        }
+    }
+
+    pub fn render(&self, camera: &Camera, world: &World) -> Canvas {
+        let width = camera.hsize as usize;
+        let height = camera.vsize as usize;
+        let mut image = Canvas::new(width, height);
+        let bar = ProgressBar::new(height as u64);
+        for y in 0..height {
+            for x in 0..width {
+                let mut rng = self.pixel_rng(x, y, width);
+                let color = self.pixel_color(camera, world, x, y, &mut rng);
+                image.canvas[y][x] = color;
+            }
+            bar.inc(1);
+        }
+        image
+    }
+
+    // every pixel gets its own generator so renders are reproducible; the
+    // seed and pixel are hashed together so neighbouring seeds do not give
+    // the same streams shifted by a pixel
+    fn pixel_rng(&self, x: usize, y: usize, width: usize) -> StdRng {
+        let index = (y * width + x) as u64;
+        StdRng::seed_from_u64(splitmix64(splitmix64(self.seed) ^ index))
+    }
+
+    // samples are always jittered inside the pixel; the camera spreads them
+    // over its lens and shutter the same way its own renderer does
+    fn pixel_color(
+        &self,
+        camera: &Camera,
+        world: &World,
+        x: usize,
+        y: usize,
+        rng: &mut StdRng,
+    ) -> Color {
+        let mut sum = Color::black();
+        for _ in 0..self.samples {
+            let px = x as f64 + rng.gen::<f64>() - 0.5;
+            let py = y as f64 + rng.gen::<f64>() - 0.5;
+            let ray = camera.lens_ray(px, py, rng);
+            sum = sum + self.radiance(world, &ray, rng);
+        }
+        sum / self.samples.max(1) as f64
+    }
+
+    pub fn radiance(&self, world: &World, r: &Ray, rng: &mut StdRng) -> Color {
+        let mut ray = Ray::with_time(r.origin, r.direction, r.time);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut radiance = Color::black();
+        // emitters reached by a diffuse bounce were already sampled directly
+        let mut count_emission = true;
+        for depth in 0..self.max_depth {
+            let Some(i) = world.closest_hit(&ray, 0.0, f64::INFINITY) else {
+                // after a diffuse bounce the environment was sampled directly
+                if count_emission || world.environment.is_none() {
+                    radiance = radiance + throughput * world.background.at(&ray.direction);
+                }
+                break;
+            };
+            // n1 and n2 only matter where the path can pass through
+            let xs = if i.object.material().transparency > 0.0 {
+                intersect_world(world, &ray)
+            } else {
+                vec![]
+            };
+            let comps = Computation::new(&i, &ray, &xs);
+            let material = comps.object.material();
+            if count_emission || comps.object.sample_surface(0.5, 0.5).is_none() {
+                radiance = radiance + throughput * material.emission();
+            }
+            count_emission = true;
+
+            let choice: f64 = rng.gen();
+            if choice < material.reflective {
+                let direction = scatter(&comps.reflectv, &comps.normalv, material.blur, rng);
+                ray = Ray::with_time(comps.over_point, direction, comps.time);
+            } else if choice < material.reflective + material.transparency {
+                ray = match refract(&comps) {
+                    Some(direction) => {
+                        let direction = scatter(&direction, &-comps.normalv, material.blur, rng);
+                        Ray::with_time(comps.under_point, direction, comps.time)
+                    }
+                    None => {
+                        let direction =
+                            scatter(&comps.reflectv, &comps.normalv, material.blur, rng);
+                        Ray::with_time(comps.over_point, direction, comps.time)
+                    }
+                };
+            } else {
+                let color = material.color_at(comps.object, &comps.over_point);
+                // next event estimation with the full BRDF, whichever lobe
+                // the path goes on to follow
+                if !world.is_occluded(&comps.over_point, &world.light.position, comps.time) {
+                    let direct = direct_lightning(
+                        &material,
+                        &color,
+                        &world.light,
+                        &comps.over_point,
+                        &comps.eyev,
+                        &comps.normalv,
+                    );
+                    radiance = radiance + throughput * direct;
+                }
+                let emitters = world.emitter_lighting(&comps, &[(rng.gen(), rng.gen())]);
+                let environment = world.environment_lighting(&comps, &[(rng.gen(), rng.gen())]);
+                let sun = world.sun_lighting(&comps);
+                radiance = radiance + throughput * (emitters + environment + sun);
+                count_emission = false;
+
+                // metals bounce off the specular lobe, everything else off the
+                // diffuse one; each is weighted by one over the chance of
+                // picking it
+                let metal = match &material.model {
+                    ShadingModel::CookTorrance(pbr) if rng.gen::<f64>() < pbr.metallic => Some(pbr),
+                    _ => None,
+                };
+                if let Some(pbr) = metal {
+                    let Some((direction, weight)) = pbr.sample_specular(
+                        &color,
+                        material.specular_ior(),
+                        &comps.eyev,
+                        &comps.normalv,
+                        rng.gen(),
+                        rng.gen(),
+                    ) else {
+                        break;
+                    };
+                    throughput = throughput * weight / pbr.metallic;
+                    ray = Ray::with_time(comps.over_point, direction, comps.time);
+                } else {
+                    // the diffuse lobe of a Cook-Torrance surface is scaled by
+                    // 1 - metallic, which cancels against the chance of taking it
+                    let albedo = match &material.model {
+                        ShadingModel::CookTorrance(_) => color,
+                        ShadingModel::Phong => color * material.diffuse,
+                    };
+                    // the cosine pdf cancels the lambertian cos / PI
+                    throughput = throughput * albedo;
+                    let direction = cosine_hemisphere(&comps.normalv, rng.gen(), rng.gen());
+                    ray = Ray::with_time(comps.over_point, direction, comps.time);
+                }
+            }
+
+            if depth >= ROULETTE_DEPTH {
+                let survival = throughput
+                    .r
+                    .max(throughput.g)
+                    .max(throughput.b)
+                    .clamp(0.05, 0.95);
+                if rng.gen::<f64>() > survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+        radiance
+    }
+}
+
+// one direction out of the lobe around `axis` on the `side` of the surface;
+// smooth surfaces keep `axis` without drawing from `rng`
+fn scatter(axis: &Vector3, side: &Vector3, blur: f64, rng: &mut StdRng) -> Vector3 {
+    if blur <= 0.0 {
+        return *axis;
+    }
+    fold_above(&glossy_lobe(axis, blur, rng.gen(), rng.gen()), side)
+}
+
+fn refract(comps: &Computation) -> Option<Vector3> {
+    let n_ratio = comps.n1 / comps.n2;
+    let cos_i = comps.eyev.dot(&comps.normalv);
+    let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+    if sin2_t > 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some((comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio).normalize())
+}
+
+#[cfg(test)]
+mod path_tracer_tests {
+    use crate::features::{
+        background::Background,
+        canvas::Canvas,
+        environment::Environment,
+        lights::Light,
+        pbr::Pbr,
+        planes::Plane,
+        shape::{Primitive, Shape},
+        spheres::Sphere,
+        transformations::translation,
+        tuple::Point3,
+    };
+
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn ray_missing_everything_is_black() {
+        let world = World::default();
+        let tracer = PathTracer::new(1);
+        let mut rng = StdRng::seed_from_u64(1);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(tracer.radiance(&world, &r, &mut rng), Color::black());
+    }
+
+    #[test]
+    fn ray_missing_everything_sees_the_background() {
+        let mut world = World::new(World::default().light, &[]);
+        world.background = Background::Solid(Color::new(0.5, 0.25, 1.0));
+        let tracer = PathTracer::new(1);
+        let mut rng = StdRng::seed_from_u64(1);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(
+            tracer.radiance(&world, &r, &mut rng),
+            Color::new(0.5, 0.25, 1.0)
+        );
+    }
+
+    #[test]
+    fn emissive_surface_seen_directly() {
+        let mut sphere = Sphere::new();
+        sphere.material.emissive = Color::new(2.0, 1.0, 0.5);
+        sphere.material.color = Color::black();
+        let light = Light::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let world = World::new(light, &[Shape::Sphere(sphere)]);
+        let tracer = PathTracer::new(1);
+        let mut rng = StdRng::seed_from_u64(7);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            tracer.radiance(&world, &r, &mut rng),
+            Color::new(2.0, 1.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn direct_light_on_open_plane() {
+        let plane = Plane::new();
+        let light = Light::new(Point3::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let world = World::new(light, &[Shape::Plane(plane)]);
+        let tracer = PathTracer::new(1);
+        let mut rng = StdRng::seed_from_u64(3);
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        // every bounce off an open plane escapes, so only direct light is left
+        let c = tracer.radiance(&world, &r, &mut rng);
+        assert_eq!(c, Color::new(0.9, 0.9, 0.9));
+    }
+
+    #[test]
+    fn sky_light_is_not_counted_twice() {
+        let mut plane = Plane::new();
+        plane.material.specular = 0.0;
+        let mut sky = Canvas::new(16, 8);
+        for row in sky.canvas.iter_mut() {
+            for pixel in row.iter_mut() {
+                *pixel = Color::new(1.0, 1.0, 1.0);
+            }
+        }
+        let environment = Environment::new(sky);
+        let light = Light::new(Point3::new(0.0, 10.0, 0.0), Color::black());
+        let mut world = World::new(light, &[Shape::Plane(plane)]);
+        world.background = environment.background();
+        world.environment = Some(environment);
+        let tracer = PathTracer::new(1);
+        let mut rng = StdRng::seed_from_u64(5);
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let n = 2000;
+        let mut sum = Color::black();
+        for _ in 0..n {
+            sum = sum + tracer.radiance(&world, &r, &mut rng);
+        }
+        let mean = sum / n as f64;
+        assert!((mean.r - 0.9).abs() < 0.9 * 0.05, "{mean:?}");
+    }
+
+    #[test]
+    fn metal_shows_a_highlight_of_the_point_light() {
+        let mut ball = Sphere::new();
+        ball.material.model =
+            ShadingModel::CookTorrance(Pbr::new(Color::new(1.0, 1.0, 1.0), 1.0, 0.3));
+        let world = World::new(World::default().light, &[Shape::Sphere(ball)]);
+        let tracer = PathTracer::new(1);
+        let mut rng = StdRng::seed_from_u64(17);
+        // looking at the sphere from the light, so the mirror direction is the light
+        let r = Ray::new(
+            Point3::new(-5.0, 5.0, -5.0),
+            Vector3::new(1.0, -1.0, 1.0).normalize(),
+        );
+        let c = tracer.radiance(&world, &r, &mut rng);
+        assert!(c.r > 0.1 && c.r == c.g && c.g == c.b, "{c:?}");
+    }
+
+    #[test]
+    fn rough_metal_blurs_what_it_reflects() {
+        let seen = |roughness: f64| {
+            let mut floor = Plane::new();
+            floor.material.model =
+                ShadingModel::CookTorrance(Pbr::new(Color::new(1.0, 1.0, 1.0), 1.0, roughness));
+            let light = Light::new(Point3::new(0.0, 10.0, 0.0), Color::black());
+            let mut world = World::new(light, &[Shape::Plane(floor)]);
+            world.background = Background::Gradient {
+                bottom: Color::black(),
+                top: Color::new(1.0, 1.0, 1.0),
+            };
+            let tracer = PathTracer::new(1);
+            let mut rng = StdRng::seed_from_u64(13);
+            let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+            let mut sum = Color::black();
+            for _ in 0..64 {
+                sum = sum + tracer.radiance(&world, &r, &mut rng);
+            }
+            sum / 64.0
+        };
+        // straight up is the brightest part of the sky
+        assert!(seen(0.0).r > 0.99);
+        assert!(seen(0.7).r < 0.95);
+    }
+
+    #[test]
+    fn color_bleeds_from_nearby_surfaces() {
+        let floor = Plane::new();
+        let mut wall = Sphere::new();
+        wall.set_transform(translation(0.0, 1.0, 1.5)).unwrap();
+        wall.material.color = Color::new(1.0, 0.0, 0.0);
+        let light = Light::new(Point3::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let world = World::new(light, &[Shape::Plane(floor), Shape::Sphere(wall)]);
+        let tracer = PathTracer::new(1);
+        let mut rng = StdRng::seed_from_u64(11);
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let mut sum = Color::black();
+        for _ in 0..256 {
+            sum = sum + tracer.radiance(&world, &r, &mut rng);
+        }
+        assert!(sum.r > sum.g * 1.05);
+        assert!((sum.g - sum.b).abs() < 0.0001);
+    }
+
+    #[test]
+    fn render_is_deterministic_under_seed() {
+        let world = World::default();
+        let mut camera = Camera::new(5.0, 5.0, PI / 3.0).unwrap();
+        camera
+            .set_transform(translation(0.0, 0.0, -5.0).inverse().unwrap())
+            .unwrap();
+        let tracer = PathTracer {
+            samples: 2,
+            max_depth: 4,
+            seed: 42,
+        };
+        assert_eq!(
+            tracer.render(&camera, &world),
+            tracer.render(&camera, &world)
+        );
+    }
+
+    #[test]
+    fn adjacent_seeds_are_not_shifted_copies() {
+        let tracer = PathTracer::new(1);
+        let next = PathTracer {
+            seed: 1,
+            ..PathTracer::new(1)
+        };
+        let a: u64 = tracer.pixel_rng(1, 0, 4).gen();
+        let b: u64 = next.pixel_rng(0, 0, 4).gen();
+        assert_ne!(a, b);
+        let c: u64 = tracer.pixel_rng(1, 0, 4).gen();
+        assert_eq!(a, c);
+    }
+}
@@ -1,80 +1,129 @@
 use std::fmt::Debug;
 
-use super::{matrice::Matrice, shape::Shape, tuple::Tuple};
+use super::{
+    error::Result,
+    matrice::Matrice4,
+    shape::Shape,
+    transformations::Transform,
+    tuple::{Color, Point3},
+};
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Pattern {
+    Solid(Color),
     Stripe(Stripe),
     Gradient(Gradient),
     Ring(Ring),
     Checker(Checker),
+    Blend(Blend),
+    #[cfg(test)]
+    Test(TestPattern),
+}
+
+impl From<Color> for Pattern {
+    fn from(color: Color) -> Self {
+        Pattern::Solid(color)
+    }
 }
 
 impl Pattern {
-    pub fn at_object(&self, shape: &Shape, point: &Tuple) -> Tuple {
-        let object_point = shape.transform().inverse().unwrap() * *point;
-        let pattern_point = self.transform().inverse().unwrap() * object_point;
+    pub fn at_object(&self, shape: &Shape, point: &Point3) -> Color {
+        let object_point = shape.inverse() * *point;
+        self.at(&object_point)
+    }
+
+    // `point` is in the space of whatever holds this pattern: object space for
+    // a material's pattern, the parent's pattern space for a nested one.
+    pub fn at(&self, point: &Point3) -> Color {
+        let pattern_point = match self.transformation() {
+            Some(transform) => transform.inverse() * *point,
+            None => *point,
+        };
         match self {
+            Pattern::Solid(color) => *color,
             Pattern::Stripe(stripe) => stripe.at(&pattern_point),
             Pattern::Gradient(gradient) => gradient.at(&pattern_point),
             Pattern::Ring(ring) => ring.at(&pattern_point),
             Pattern::Checker(checker) => checker.at(&pattern_point),
+            Pattern::Blend(blend) => blend.at(&pattern_point),
+            #[cfg(test)]
+            Pattern::Test(test) => test.at(&pattern_point),
         }
     }
 
-    pub fn transform(&self) -> Matrice {
+    pub fn transform(&self) -> Matrice4 {
+        match self.transformation() {
+            Some(transform) => *transform.matrix(),
+            None => Matrice4::identity(),
+        }
+    }
+    // solid colors look the same everywhere and carry no transform
+    fn transformation(&self) -> Option<&Transform> {
         match self {
-            Pattern::Stripe(stripe) => stripe.transform.clone(),
-            Pattern::Gradient(gradient) => gradient.transform.clone(),
-            Pattern::Ring(ring) => ring.transform.clone(),
-            Pattern::Checker(checker) => checker.transform.clone(),
+            Pattern::Solid(_) => None,
+            Pattern::Stripe(stripe) => Some(&stripe.transform),
+            Pattern::Gradient(gradient) => Some(&gradient.transform),
+            Pattern::Ring(ring) => Some(&ring.transform),
+            Pattern::Checker(checker) => Some(&checker.transform),
+            Pattern::Blend(blend) => Some(&blend.transform),
+            #[cfg(test)]
+            Pattern::Test(test) => Some(&test.transform),
         }
     }
-    pub fn set_transform(&mut self, transform: Matrice) {
+    pub fn set_transform(&mut self, transform: Matrice4) -> Result<()> {
         match self {
+            Pattern::Solid(_) => Ok(()),
             Pattern::Stripe(stripe) => stripe.set_transform(transform),
             Pattern::Gradient(gradient) => gradient.set_transform(transform),
             Pattern::Ring(ring) => ring.set_transform(transform),
             Pattern::Checker(checker) => checker.set_transform(transform),
+            Pattern::Blend(blend) => blend.set_transform(transform),
+            #[cfg(test)]
+            Pattern::Test(test) => {
+                test.transform = Transform::new(transform)?;
+                Ok(())
+            }
         }
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Stripe {
-    pub a: Tuple,
-    pub b: Tuple,
-    transform: Matrice,
+    pub a: Box<Pattern>,
+    pub b: Box<Pattern>,
+    transform: Transform,
 }
 
 impl Stripe {
-    pub fn new(a: Tuple, b: Tuple) -> Self {
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
         Self {
-            a,
-            b,
-            transform: Matrice::identity_matrix(4),
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transform: Transform::default(),
         }
     }
-    pub fn at(&self, point: &Tuple) -> Tuple {
+    pub fn at(&self, point: &Point3) -> Color {
         if point.x.floor() as i32 % 2 == 0 {
-            return self.a;
+            return self.a.at(point);
         }
-        self.b
+        self.b.at(point)
     }
-    pub fn at_object(&self, object: &Shape, point: &Tuple) -> Tuple {
-        let object_point = object.transform().inverse().unwrap() * *point;
-        let pattern_point = self.transform.inverse().unwrap() * object_point;
+    pub fn at_object(&self, object: &Shape, point: &Point3) -> Color {
+        let object_point = object.inverse() * *point;
+        let pattern_point = self.transform.inverse() * object_point;
         self.at(&pattern_point)
     }
 
-    pub fn set_transform(&mut self, tranform: Matrice) {
-        self.transform = tranform;
+    pub fn set_transform(&mut self, transform: Matrice4) -> Result<()> {
+        self.transform = Transform::new(transform)?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod patterns_tests {
     use crate::features::{
+        shape::Primitive,
         spheres::Sphere,
         transformations::{scaling, translation},
     };
@@ -82,111 +131,113 @@ mod patterns_tests {
     use super::*;
     #[test]
     fn create_pattern() {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         let p = Stripe::new(white, black);
-        assert_eq!(p.a, white);
-        assert_eq!(p.b, black);
+        assert_eq!(*p.a, Pattern::Solid(white));
+        assert_eq!(*p.b, Pattern::Solid(black));
     }
 
     #[test]
     fn stripe_pattern_constant_in_y() {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         let p = Stripe::new(white, black);
-        assert_eq!(p.at(&Tuple::point(0.0, 0.0, 0.0)), white);
-        assert_eq!(p.at(&Tuple::point(0.0, 1.0, 0.0)), white);
-        assert_eq!(p.at(&Tuple::point(0.0, 2.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.0, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.0, 1.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.0, 2.0, 0.0)), white);
     }
 
     #[test]
     fn stripe_pattern_constant_in_z() {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         let p = Stripe::new(white, black);
-        assert_eq!(p.at(&Tuple::point(0.0, 0.0, 0.0)), white);
-        assert_eq!(p.at(&Tuple::point(0.0, 0.0, 1.0)), white);
-        assert_eq!(p.at(&Tuple::point(0.0, 0.0, 2.0)), white);
+        assert_eq!(p.at(&Point3::new(0.0, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.0, 0.0, 1.0)), white);
+        assert_eq!(p.at(&Point3::new(0.0, 0.0, 2.0)), white);
     }
     #[test]
     fn stripe_pattern_alternates_in_x() {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         let p = Stripe::new(white, black);
-        assert_eq!(p.at(&Tuple::point(0.0, 0.0, 0.0)), white);
-        assert_eq!(p.at(&Tuple::point(0.9, 0.0, 0.0)), white);
-        assert_eq!(p.at(&Tuple::point(1.0, 0.0, 0.0)), black);
-        assert_eq!(p.at(&Tuple::point(-0.1, 0.0, 0.0)), black);
-        assert_eq!(p.at(&Tuple::point(-1.0, 0.0, 0.0)), black);
-        assert_eq!(p.at(&Tuple::point(-1.1, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.0, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.9, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(1.0, 0.0, 0.0)), black);
+        assert_eq!(p.at(&Point3::new(-0.1, 0.0, 0.0)), black);
+        assert_eq!(p.at(&Point3::new(-1.0, 0.0, 0.0)), black);
+        assert_eq!(p.at(&Point3::new(-1.1, 0.0, 0.0)), white);
     }
     #[test]
     fn stripes_with_object_transform() {
         let pattern = default_pattern();
         let object = {
             let mut object = Sphere::new();
-            object.set_transform(scaling(2.0, 2.0, 2.0));
+            object.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
             object
         };
-        let c = pattern.at_object(&Shape::Sphere(object), &Tuple::point(1.5, 0.0, 0.0));
-        assert_eq!(c, Tuple::color(1.0, 1.0, 1.0))
+        let c = pattern.at_object(&Shape::Sphere(object), &Point3::new(1.5, 0.0, 0.0));
+        assert_eq!(c, Color::new(1.0, 1.0, 1.0))
     }
 
     #[test]
     fn stripes_with_pattern_transformation() {
         let object = Sphere::new();
         let mut pattern = default_pattern();
-        pattern.set_transform(scaling(2.0, 2.0, 2.0));
-        let c = pattern.at_object(&Shape::Sphere(object), &Tuple::point(1.5, 0.0, 0.0));
-        assert_eq!(c, Tuple::color(1.0, 1.0, 1.0))
+        pattern.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let c = pattern.at_object(&Shape::Sphere(object), &Point3::new(1.5, 0.0, 0.0));
+        assert_eq!(c, Color::new(1.0, 1.0, 1.0))
     }
 
     #[test]
     fn stripes_with_pattern_and_object_transformation() {
         let mut object = Sphere::new();
-        object.set_transform(scaling(2.0, 2.0, 2.0));
+        object.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
         let mut pattern = default_pattern();
-        pattern.set_transform(translation(0.5, 0.0, 0.0));
-        let c = pattern.at_object(&Shape::Sphere(object), &Tuple::point(2.5, 0.0, 0.0));
-        assert_eq!(c, Tuple::color(1.0, 1.0, 1.0))
+        pattern.set_transform(translation(0.5, 0.0, 0.0)).unwrap();
+        let c = pattern.at_object(&Shape::Sphere(object), &Point3::new(2.5, 0.0, 0.0));
+        assert_eq!(c, Color::new(1.0, 1.0, 1.0))
     }
 
     fn default_pattern() -> Stripe {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         Stripe::new(white, black)
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Gradient {
-    from_color: Tuple,
-    to_color: Tuple,
-    transform: Matrice,
+    from: Box<Pattern>,
+    to: Box<Pattern>,
+    transform: Transform,
 }
 
 impl Gradient {
-    pub fn new(from: Tuple, to: Tuple) -> Self {
+    pub fn new(from: impl Into<Pattern>, to: impl Into<Pattern>) -> Self {
         Self {
-            from_color: from,
-            to_color: to,
-            transform: Matrice::identity_matrix(4),
+            from: Box::new(from.into()),
+            to: Box::new(to.into()),
+            transform: Transform::default(),
         }
     }
 
-    pub fn at(&self, point: &Tuple) -> Tuple {
-        let distance = self.to_color - self.from_color;
+    pub fn at(&self, point: &Point3) -> Color {
+        let from_color = self.from.at(point);
+        let distance = self.to.at(point) - from_color;
         let fraction = point.x - point.x.floor();
-        self.from_color + distance * fraction
+        from_color + distance * fraction
     }
-    pub fn set_transform(&mut self, tranform: Matrice) {
-        self.transform = tranform;
+    pub fn set_transform(&mut self, transform: Matrice4) -> Result<()> {
+        self.transform = Transform::new(transform)?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod gradient_tests {
-    use crate::features::tuple::Tuple;
+    use crate::features::tuple::{Color, Point3};
 
     use super::Gradient;
 
@@ -194,63 +245,64 @@ mod gradient_tests {
     fn gradient_test() {
         let pattern = default_gradient();
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 0.0)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.0, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.25, 0.0, 0.0)),
-            Tuple::color(0.75, 0.75, 0.75)
+            pattern.at(&Point3::new(0.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.5, 0.0, 0.0)),
-            Tuple::color(0.5, 0.5, 0.5)
+            pattern.at(&Point3::new(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.75, 0.0, 0.0)),
-            Tuple::color(0.25, 0.25, 0.25)
+            pattern.at(&Point3::new(0.75, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
         );
     }
 
     fn default_gradient() -> Gradient {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         Gradient::new(white, black)
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Ring {
-    a: Tuple,
-    b: Tuple,
-    transform: Matrice,
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    transform: Transform,
 }
 
 impl Ring {
-    pub fn new(a: Tuple, b: Tuple) -> Ring {
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Ring {
         Ring {
-            a,
-            b,
-            transform: Matrice::identity_matrix(4),
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transform: Transform::default(),
         }
     }
 
-    pub fn set_transform(&mut self, transform: Matrice) {
-        self.transform = transform
+    pub fn set_transform(&mut self, transform: Matrice4) -> Result<()> {
+        self.transform = Transform::new(transform)?;
+        Ok(())
     }
-    pub fn transform(&self) -> Matrice {
-        self.transform.clone()
+    pub fn transform(&self) -> Matrice4 {
+        *self.transform.matrix()
     }
-    pub fn at(&self, point: &Tuple) -> Tuple {
+    pub fn at(&self, point: &Point3) -> Color {
         if (point.x * point.x + point.z * point.z).sqrt() as i32 % 2 == 0 {
-            return self.a;
+            return self.a.at(point);
         }
-        self.b
+        self.b.at(point)
     }
 }
 
 #[cfg(test)]
 mod ring_tests {
-    use crate::features::tuple::Tuple;
+    use crate::features::tuple::{Color, Point3};
 
     use super::Ring;
 
@@ -258,62 +310,63 @@ mod ring_tests {
     fn test_ring() {
         let pattern = default_ring();
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 0.0)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.0, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(1.0, 0.0, 0.0)),
-            Tuple::color(0.0, 0.0, 0.0)
+            pattern.at(&Point3::new(1.0, 0.0, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 1.0)),
-            Tuple::color(0.0, 0.0, 0.0)
+            pattern.at(&Point3::new(0.0, 0.0, 1.0)),
+            Color::new(0.0, 0.0, 0.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.708, 0.0, 0.708)),
-            Tuple::color(0.0, 0.0, 0.0)
+            pattern.at(&Point3::new(0.708, 0.0, 0.708)),
+            Color::new(0.0, 0.0, 0.0)
         );
     }
     fn default_ring() -> Ring {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         Ring::new(white, black)
     }
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Checker {
-    a: Tuple,
-    b: Tuple,
-    transform: Matrice,
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    transform: Transform,
 }
 
 impl Checker {
-    pub fn new(a: Tuple, b: Tuple) -> Self {
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
         Self {
-            a,
-            b,
-            transform: Matrice::identity_matrix(4),
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            transform: Transform::default(),
         }
     }
-    pub fn set_transform(&mut self, transform: Matrice) {
-        self.transform = transform
+    pub fn set_transform(&mut self, transform: Matrice4) -> Result<()> {
+        self.transform = Transform::new(transform)?;
+        Ok(())
     }
-    pub fn transform(&self) -> Matrice {
-        self.transform.clone()
+    pub fn transform(&self) -> Matrice4 {
+        *self.transform.matrix()
     }
-    pub fn at(&self, point: &Tuple) -> Tuple {
+    pub fn at(&self, point: &Point3) -> Color {
         if (point.x.abs() + point.y.abs() + point.z.abs()) as i32 % 2 == 0 {
-            return self.a;
+            return self.a.at(point);
         }
-        self.b
+        self.b.at(point)
     }
 }
 
 #[cfg(test)]
 mod checker_tests {
 
-    use crate::features::tuple::Tuple;
+    use crate::features::tuple::{Color, Point3};
 
     use super::Checker;
 
@@ -321,32 +374,32 @@ mod checker_tests {
     fn test_checker_repeat_in_x() {
         let pattern = default_checker();
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 0.0)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.0, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.99, 0.0, 0.0)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.99, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(1.01, 0.0, 0.0)),
-            Tuple::color(0.0, 0.0, 0.0)
+            pattern.at(&Point3::new(1.01, 0.0, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
         );
     }
     #[test]
     fn test_checker_repeat_in_y() {
         let pattern = default_checker();
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 0.0)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.0, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.99, 0.0)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.0, 0.99, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 1.01, 0.0)),
-            Tuple::color(0.0, 0.0, 0.0)
+            pattern.at(&Point3::new(0.0, 1.01, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
         );
     }
 
@@ -354,22 +407,191 @@ mod checker_tests {
     fn test_checker_repeat_in_z() {
         let pattern = default_checker();
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 0.0)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.0, 0.0, 0.0)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 0.99)),
-            Tuple::color(1.0, 1.0, 1.0)
+            pattern.at(&Point3::new(0.0, 0.0, 0.99)),
+            Color::new(1.0, 1.0, 1.0)
         );
         assert_eq!(
-            pattern.at(&Tuple::point(0.0, 0.0, 1.01)),
-            Tuple::color(0.0, 0.0, 0.0)
+            pattern.at(&Point3::new(0.0, 0.0, 1.01)),
+            Color::new(0.0, 0.0, 0.0)
         );
     }
 
     fn default_checker() -> Checker {
-        let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-        let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
+        let white: Color = Color::new(1.0, 1.0, 1.0);
+        let black: Color = Color::new(0.0, 0.0, 0.0);
         Checker::new(white, black)
     }
 }
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Blend {
+    a: Box<Pattern>,
+    b: Box<Pattern>,
+    weight: f64,
+    transform: Transform,
+}
+
+impl Blend {
+    // weight 0.0 is all `a`, 1.0 is all `b`
+    pub fn new(a: impl Into<Pattern>, b: impl Into<Pattern>, weight: f64) -> Self {
+        Self {
+            a: Box::new(a.into()),
+            b: Box::new(b.into()),
+            weight,
+            transform: Transform::default(),
+        }
+    }
+    pub fn average(a: impl Into<Pattern>, b: impl Into<Pattern>) -> Self {
+        Self::new(a, b, 0.5)
+    }
+    pub fn set_transform(&mut self, transform: Matrice4) -> Result<()> {
+        self.transform = Transform::new(transform)?;
+        Ok(())
+    }
+    pub fn transform(&self) -> Matrice4 {
+        *self.transform.matrix()
+    }
+    pub fn at(&self, point: &Point3) -> Color {
+        self.a.at(point) * (1.0 - self.weight) + self.b.at(point) * self.weight
+    }
+}
+
+#[cfg(test)]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct TestPattern {
+    transform: Transform,
+}
+
+#[cfg(test)]
+impl TestPattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn at(&self, point: &Point3) -> Color {
+        Color::new(point.x, point.y, point.z)
+    }
+}
+
+#[cfg(test)]
+mod nested_tests {
+    use crate::features::{
+        spheres::Sphere,
+        transformations::{scaling, translation},
+    };
+
+    use super::*;
+
+    #[test]
+    fn solid_pattern_ignores_point() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let pattern = Pattern::from(red);
+        assert_eq!(pattern.at(&Point3::new(0.0, 0.0, 0.0)), red);
+        assert_eq!(pattern.at(&Point3::new(-3.5, 2.0, 7.1)), red);
+    }
+
+    #[test]
+    fn stripe_of_stripes() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let red = Color::new(1.0, 0.0, 0.0);
+        let mut inner = Pattern::Stripe(Stripe::new(white, black));
+        inner.set_transform(scaling(0.5, 0.5, 0.5)).unwrap();
+        let p = Stripe::new(inner, red);
+        assert_eq!(p.at(&Point3::new(0.25, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.75, 0.0, 0.0)), black);
+        assert_eq!(p.at(&Point3::new(1.25, 0.0, 0.0)), red);
+    }
+
+    #[test]
+    fn checker_of_stripes() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let red = Color::new(1.0, 0.0, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let p = Checker::new(
+            Pattern::Stripe(Stripe::new(white, black)),
+            Pattern::Stripe(Stripe::new(red, green)),
+        );
+        assert_eq!(p.at(&Point3::new(0.5, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(1.5, 0.0, 0.0)), green);
+        assert_eq!(p.at(&Point3::new(2.5, 0.0, 0.0)), white);
+        assert_eq!(p.at(&Point3::new(0.5, 1.2, 0.0)), red);
+    }
+
+    #[test]
+    fn nested_pattern_keeps_own_transform() {
+        let mut inner = Pattern::Test(TestPattern::new());
+        inner.set_transform(translation(1.0, 0.0, 0.0)).unwrap();
+        let mut outer = Pattern::Checker(Checker::new(inner, Color::new(0.0, 0.0, 0.0)));
+        outer.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let object = Sphere::new();
+        let c = outer.at_object(&Shape::Sphere(object), &Point3::new(1.0, 0.5, 0.0));
+        assert_eq!(c, Color::new(-0.5, 0.25, 0.0));
+    }
+
+    #[test]
+    fn gradient_between_patterns() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let p = Gradient::new(Pattern::Stripe(Stripe::new(white, black)), black);
+        assert_eq!(
+            p.at(&Point3::new(0.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+    }
+}
+
+#[cfg(test)]
+mod blend_tests {
+    use crate::features::transformations::{rotation_y, scaling};
+
+    use super::*;
+
+    #[test]
+    fn blend_averages_sub_patterns() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let p = Blend::average(white, black);
+        assert_eq!(p.at(&Point3::new(0.0, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn blend_respects_weight() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        let p = Blend::new(red, blue, 0.25);
+        assert_eq!(
+            p.at(&Point3::new(0.0, 0.0, 0.0)),
+            Color::new(0.75, 0.0, 0.25)
+        );
+    }
+
+    #[test]
+    fn blend_of_crossed_stripes() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let along_x = Pattern::Stripe(Stripe::new(white, black));
+        let mut along_z = Pattern::Stripe(Stripe::new(white, black));
+        along_z
+            .set_transform(rotation_y(std::f64::consts::PI / 2.0))
+            .unwrap();
+        let mut p = Pattern::Blend(Blend::average(along_x, along_z));
+        p.set_transform(scaling(0.5, 0.5, 0.5)).unwrap();
+        assert_eq!(
+            p.at(&Point3::new(0.25, 0.0, -0.25)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            p.at(&Point3::new(0.75, 0.0, -0.25)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            p.at(&Point3::new(0.75, 0.0, -0.75)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use std::f64::consts::PI;
+
+use super::{
+    lights::Light,
+    sampling::orthonormal_basis,
+    tuple::{Color, Point3, Vector3},
+};
+
+// the index of refraction of a typical dielectric such as plastic or glass,
+// which reflects about 4% of the light at normal incidence
+pub const DIELECTRIC_IOR: f64 = 1.5;
+
+// metallic/roughness parameters for the Cook-Torrance model; dielectric
+// reflectance comes from the material's `specular_ior`
+#[derive(PartialEq, Debug, Clone)]
+pub struct Pbr {
+    // the surface color where the material has no pattern. The methods below
+    // take the color found at the hit instead, as `Material::color_at`
+    // resolves it, so patterns tint metals and diffuse light alike.
+    pub base_color: Color,
+    pub metallic: f64,
+    // width of the GGX microfacet lobe, for highlights in both renderers and
+    // for the bounces the path tracer takes off metal
+    pub roughness: f64,
+}
+
+impl Pbr {
+    pub fn new(base_color: Color, metallic: f64, roughness: f64) -> Self {
+        Self {
+            base_color,
+            metallic,
+            roughness,
+        }
+    }
+
+    // reflectance at normal incidence: derived from the material's index of
+    // refraction for dielectrics and tinted by the surface color for metals
+    pub fn f0(&self, color: &Color, ior: f64) -> Color {
+        let r = ((ior - 1.0) / (ior + 1.0)).powi(2);
+        let dielectric = Color::new(r, r, r);
+        dielectric * (1.0 - self.metallic) + *color * self.metallic
+    }
+
+    // direct light reflected towards the eye, without the ambient term. The
+    // result is scaled by PI so a white lambertian surface lit head-on by a
+    // light of intensity 1 comes out white, like the Phong model does.
+    pub fn shade(
+        &self,
+        color: &Color,
+        ior: f64,
+        light: &Light,
+        position: &Point3,
+        eyev: &Vector3,
+        normalv: &Vector3,
+    ) -> Color {
+        let lightv = (light.position - *position).normalize();
+        let n_dot_l = normalv.dot(&lightv);
+        let n_dot_v = normalv.dot(eyev).max(0.0001);
+        if n_dot_l <= 0.0 {
+            return Color::black();
+        }
+        let halfway = (lightv + *eyev).normalize();
+        let n_dot_h = normalv.dot(&halfway).max(0.0);
+        let v_dot_h = eyev.dot(&halfway).max(0.0);
+
+        let roughness = self.roughness.clamp(0.01, 1.0);
+        let d = ggx_distribution(n_dot_h, roughness);
+        let g = smith_geometry(n_dot_v, roughness) * smith_geometry(n_dot_l, roughness);
+        let f = fresnel_schlick(v_dot_h, &self.f0(color, ior));
+
+        let specular = f * (d * g / (4.0 * n_dot_v * n_dot_l));
+        let white = Color::new(1.0, 1.0, 1.0);
+        let kd = (white - f) * (1.0 - self.metallic);
+        let diffuse = kd * *color / PI;
+        (diffuse + specular) * light.intensity * (n_dot_l * PI)
+    }
+
+    // one bounce off the specular lobe, for the path tracer: the direction
+    // light arrives from to leave towards `eyev`, and the weight of that
+    // sample, the BRDF times the cosine over the pdf. None when the sampled
+    // microfacet sends the light into the surface.
+    pub fn sample_specular(
+        &self,
+        color: &Color,
+        ior: f64,
+        eyev: &Vector3,
+        normalv: &Vector3,
+        u1: f64,
+        u2: f64,
+    ) -> Option<(Vector3, Color)> {
+        let roughness = self.roughness.clamp(0.01, 1.0);
+        let halfway = ggx_halfway(normalv, roughness, u1, u2);
+        let v_dot_h = eyev.dot(&halfway);
+        if v_dot_h <= 0.0 {
+            return None;
+        }
+        let lightv = halfway * (2.0 * v_dot_h) - *eyev;
+        let n_dot_l = normalv.dot(&lightv);
+        if n_dot_l <= 0.0 {
+            return None;
+        }
+        let n_dot_v = normalv.dot(eyev).max(0.0001);
+        let n_dot_h = normalv.dot(&halfway).max(0.0001);
+        let g = smith_geometry(n_dot_v, roughness) * smith_geometry(n_dot_l, roughness);
+        let f = fresnel_schlick(v_dot_h, &self.f0(color, ior));
+        // D cancels against the pdf of the halfway vector, D (n.h) / 4 (v.h)
+        Some((lightv, f * (g * v_dot_h / (n_dot_v * n_dot_h))))
+    }
+}
+
+// maps two uniform numbers in [0, 1) to a microfacet normal around `normal`
+// with a density of `ggx_distribution` times the cosine to the normal
+pub fn ggx_halfway(normal: &Vector3, roughness: f64, u1: f64, u2: f64) -> Vector3 {
+    let a2 = roughness.powi(4);
+    let cos_theta = ((1.0 - u2) / (1.0 + (a2 - 1.0) * u2)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u1;
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + *normal * cos_theta)
+        .normalize()
+}
+
+// Trowbridge-Reitz normal distribution with alpha = roughness^2
+pub fn ggx_distribution(n_dot_h: f64, roughness: f64) -> f64 {
+    let a2 = roughness.powi(4);
+    let denom = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * denom * denom)
+}
+
+// Schlick-GGX masking term for a single direction, k remapped for direct light
+pub fn smith_geometry(n_dot_x: f64, roughness: f64) -> f64 {
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    n_dot_x / (n_dot_x * (1.0 - k) + k)
+}
+
+pub fn fresnel_schlick(cos_theta: f64, f0: &Color) -> Color {
+    let white = Color::new(1.0, 1.0, 1.0);
+    *f0 + (white - *f0) * (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
+#[cfg(test)]
+mod pbr_tests {
+    use super::*;
+
+    fn head_on() -> (Light, Point3, Vector3, Vector3) {
+        let light = Light::new(Point3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let position = Point3::new(0.0, 0.0, 0.0);
+        let eyev = Vector3::new(0.0, 0.0, -1.0);
+        let normalv = Vector3::new(0.0, 0.0, -1.0);
+        (light, position, eyev, normalv)
+    }
+
+    #[test]
+    fn dielectric_f0_from_ior() {
+        let pbr = Pbr::new(Color::new(1.0, 0.0, 0.0), 0.0, 0.5);
+        assert_eq!(pbr.f0(&pbr.base_color, 1.5), Color::new(0.04, 0.04, 0.04));
+        assert_eq!(pbr.f0(&pbr.base_color, 1.0), Color::black());
+    }
+
+    #[test]
+    fn metal_f0_is_base_color() {
+        let gold = Color::new(1.0, 0.71, 0.29);
+        let pbr = Pbr::new(gold, 1.0, 0.5);
+        assert_eq!(pbr.f0(&gold, 1.5), gold);
+    }
+
+    #[test]
+    fn metal_f0_follows_the_color_at_the_hit() {
+        let pbr = Pbr::new(Color::new(1.0, 0.71, 0.29), 1.0, 0.5);
+        let stripe = Color::new(0.2, 0.4, 0.6);
+        assert_eq!(pbr.f0(&stripe, 1.5), stripe);
+    }
+
+    #[test]
+    fn fresnel_goes_to_white_at_grazing_angles() {
+        let f0 = Color::new(0.04, 0.04, 0.04);
+        assert_eq!(fresnel_schlick(1.0, &f0), f0);
+        assert_eq!(fresnel_schlick(0.0, &f0), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn ggx_distribution_normalized_for_smooth_peak() {
+        let smooth = ggx_distribution(1.0, 0.1);
+        let rough = ggx_distribution(1.0, 0.9);
+        assert!(smooth > rough);
+        assert!((ggx_distribution(1.0, 1.0) - 1.0 / PI).abs() < 0.00001);
+    }
+
+    #[test]
+    fn rough_dielectric_is_mostly_diffuse() {
+        let (light, position, eyev, normalv) = head_on();
+        let base = Color::new(0.8, 0.2, 0.2);
+        let pbr = Pbr::new(base, 0.0, 1.0);
+        let c = pbr.shade(&base, 1.5, &light, &position, &eyev, &normalv);
+        assert!(c.r > c.g && c.g == c.b);
+        assert!(c.r < 1.0);
+        assert!(c.r > 0.75);
+    }
+
+    #[test]
+    fn metal_has_no_diffuse_lobe() {
+        let (light, position, _, normalv) = head_on();
+        let base = Color::new(0.9, 0.9, 0.9);
+        let pbr = Pbr::new(base, 1.0, 0.2);
+        // the eye far away from the mirror direction sees almost nothing
+        let eyev = Vector3::new(0.0, 0.9, -0.1).normalize();
+        let c = pbr.shade(&base, 1.5, &light, &position, &eyev, &normalv);
+        assert!(c.r < 0.01);
+    }
+
+    #[test]
+    fn smooth_metal_samples_the_mirror_direction() {
+        let (_, _, eyev, normalv) = head_on();
+        let gold = Color::new(1.0, 0.71, 0.29);
+        let pbr = Pbr::new(gold, 1.0, 0.0);
+        let (lightv, weight) = pbr
+            .sample_specular(&gold, 1.5, &eyev, &normalv, 0.3, 0.5)
+            .unwrap();
+        assert!(lightv.dot(&eyev) > 0.999);
+        assert!((weight.r - 1.0).abs() < 0.01 && (weight.b - 0.29).abs() < 0.01);
+    }
+
+    #[test]
+    fn rough_metal_spreads_its_samples() {
+        let (_, _, eyev, normalv) = head_on();
+        let base = Color::new(0.9, 0.9, 0.9);
+        let spread = |roughness: f64| {
+            let pbr = Pbr::new(base, 1.0, roughness);
+            let mut sum = 0.0;
+            for i in 0..16 {
+                for j in 0..16 {
+                    let (u1, u2) = (i as f64 / 16.0, (j as f64 + 0.5) / 16.0);
+                    if let Some((lightv, _)) =
+                        pbr.sample_specular(&base, 1.5, &eyev, &normalv, u1, u2)
+                    {
+                        sum += 1.0 - lightv.dot(&eyev);
+                    }
+                }
+            }
+            sum
+        };
+        assert!(spread(0.2) < spread(0.8));
+    }
+
+    #[test]
+    fn light_behind_surface_contributes_nothing() {
+        let (_, position, eyev, normalv) = head_on();
+        let light = Light::new(Point3::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+        let base = Color::new(1.0, 1.0, 1.0);
+        let pbr = Pbr::new(base, 0.0, 0.5);
+        let c = pbr.shade(&base, 1.5, &light, &position, &eyev, &normalv);
+        assert_eq!(c, Color::black());
+    }
+}
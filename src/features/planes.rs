@@ -1,41 +1,63 @@
-use crate::features::{rays::transform, shape::Shape};
-
 use super::{
-    intersections::Intersection, materials::Material, matrice::Matrice, rays::Ray, tuple::Tuple,
+    materials::Material,
+    motion::Motion,
+    rays::Ray,
+    shape::Primitive,
+    transformations::Transform,
+    tuple::{Point3, Vector3, EPSILON},
 };
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Plane {
     pub material: Material,
-    pub transform: Matrice,
+    transform: Transform,
+    pub motion: Option<Motion>,
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Plane {
     pub fn new() -> Self {
         Plane {
             material: Material::new(),
-            transform: Matrice::identity_matrix(4),
+            transform: Transform::default(),
+            motion: None,
         }
     }
+}
 
-    pub fn normal_at(&self, _point: Tuple) -> Tuple {
-        self.transform.clone() * Tuple::vector(0.0, 1.0, 0.0)
+// the xz plane, infinite in both directions
+impl Primitive for Plane {
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        if ray.direction.y.abs() < EPSILON {
+            return vec![];
+        }
+        vec![(-ray.origin.y) / ray.direction.y]
     }
-
-    pub fn set_transform(&mut self, transform: Matrice) {
-        self.transform = transform
+    fn local_normal_at(&self, _point: Point3) -> Vector3 {
+        Vector3::new(0.0, 1.0, 0.0)
     }
-
-    pub fn transform(&self) -> Matrice {
-        self.transform.clone()
+    fn transformation(&self) -> &Transform {
+        &self.transform
     }
-    pub fn intersect(&self, ray: &Ray) -> Vec<Intersection> {
-        // let r = transform(ray.clone(), self.transform.clone());
-        if ray.direction.y.abs() < 0.00001 {
-            return vec![];
-        }
-        let t = (-ray.origin.y) / ray.direction.y;
-        vec![Intersection::new(t, Shape::Plane(self.clone()))]
+    fn transformation_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn material(&self) -> &Material {
+        &self.material
+    }
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+    fn motion_mut(&mut self) -> &mut Option<Motion> {
+        &mut self.motion
     }
 }
 
@@ -48,52 +70,52 @@ mod plane_tests {
     fn constant_normal_everywhere() {
         let p = Plane::new();
         assert_eq!(
-            p.normal_at(Tuple::point(0.0, 0.0, 0.0)),
-            Tuple::vector(0.0, 1.0, 0.0)
+            p.normal_at(Point3::new(0.0, 0.0, 0.0)),
+            Vector3::new(0.0, 1.0, 0.0)
         );
         assert_eq!(
-            p.normal_at(Tuple::point(10.0, 0.0, -10.0)),
-            Tuple::vector(0.0, 1.0, 0.0)
+            p.normal_at(Point3::new(10.0, 0.0, -10.0)),
+            Vector3::new(0.0, 1.0, 0.0)
         );
         assert_eq!(
-            p.normal_at(Tuple::point(-5.0, 0.0, 150.0)),
-            Tuple::vector(0.0, 1.0, 0.0)
+            p.normal_at(Point3::new(-5.0, 0.0, 150.0)),
+            Vector3::new(0.0, 1.0, 0.0)
         );
     }
 
     #[test]
     fn intersect_ray_parallel_to_plane() {
         let p = Plane::new();
-        let r = Ray::new(Tuple::point(0.0, 10.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = p.intersect(&r);
+        let r = Ray::new(Point3::new(0.0, 10.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = p.local_intersect(&r);
         assert_eq!(xs.len(), 0)
     }
 
     #[test]
     fn intersect_coplanar_ray() {
         let p = Plane::new();
-        let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
-        let xs = p.intersect(&r);
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = p.local_intersect(&r);
         assert_eq!(xs.len(), 0);
     }
 
     #[test]
     fn ray_intersecting_plane_from_above() {
-        let p = Plane::new();
-        let r = Ray::new(Tuple::point(0.0, 1.0, 0.0), Tuple::vector(0.0, -1.0, 0.0));
+        let p = Shape::Plane(Plane::new());
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
         let xs = p.intersect(&r);
         assert_eq!(xs.len(), 1);
         assert_eq!(xs[0].t, 1.0);
-        assert_eq!(xs[0].s, Shape::Plane(p))
+        assert!(xs[0].is_on(&p));
     }
 
     #[test]
     fn ray_intersecting_plane_from_below() {
-        let p = Plane::new();
-        let r = Ray::new(Tuple::point(0.0, -1.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+        let p = Shape::Plane(Plane::new());
+        let r = Ray::new(Point3::new(0.0, -1.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
         let xs = p.intersect(&r);
         assert_eq!(xs.len(), 1);
         assert_eq!(xs[0].t, 1.0);
-        assert_eq!(xs[0].s, Shape::Plane(p))
+        assert!(xs[0].is_on(&p));
     }
 }
@@ -0,0 +1,111 @@
+use super::{
+    materials::Material,
+    motion::Motion,
+    rays::Ray,
+    shape::Primitive,
+    transformations::Transform,
+    tuple::{Point3, Vector3, EPSILON},
+};
+
+// the square of the xz plane between -1 and 1 on both axes, facing +y
+#[derive(Clone, PartialEq, Debug)]
+pub struct Quad {
+    pub material: Material,
+    transform: Transform,
+    pub motion: Option<Motion>,
+}
+
+impl Default for Quad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Quad {
+    pub fn new() -> Self {
+        Quad {
+            material: Material::new(),
+            transform: Transform::default(),
+            motion: None,
+        }
+    }
+}
+
+impl Primitive for Quad {
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        if ray.direction.y.abs() < EPSILON {
+            return vec![];
+        }
+        let t = (-ray.origin.y) / ray.direction.y;
+        let point = ray.position(t);
+        if point.x.abs() > 1.0 || point.z.abs() > 1.0 {
+            return vec![];
+        }
+        vec![t]
+    }
+    fn local_normal_at(&self, _point: Point3) -> Vector3 {
+        Vector3::new(0.0, 1.0, 0.0)
+    }
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+    fn transformation_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn material(&self) -> &Material {
+        &self.material
+    }
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+    fn motion_mut(&mut self) -> &mut Option<Motion> {
+        &mut self.motion
+    }
+    fn local_sample(&self, u: f64, v: f64) -> Option<(Point3, Vector3, f64)> {
+        Some((
+            Point3::new(u * 2.0 - 1.0, 0.0, v * 2.0 - 1.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            4.0,
+        ))
+    }
+    fn local_uv(&self, point: Point3) -> (f64, f64) {
+        ((point.x + 1.0) / 2.0, (point.z + 1.0) / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod quad_tests {
+    use crate::features::transformations::{rotation_x, scaling};
+
+    use super::*;
+
+    #[test]
+    fn ray_hits_inside_quad() {
+        let q = Quad::new();
+        let r = Ray::new(Point3::new(0.5, 1.0, -0.5), Vector3::new(0.0, -1.0, 0.0));
+        let xs = q.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0], 1.0);
+    }
+
+    #[test]
+    fn ray_misses_outside_quad() {
+        let q = Quad::new();
+        let r = Ray::new(Point3::new(1.5, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        assert_eq!(q.local_intersect(&r).len(), 0);
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(q.local_intersect(&r).len(), 0);
+    }
+
+    #[test]
+    fn transformed_quad_normal() {
+        let mut q = Quad::new();
+        q.set_transform(rotation_x(std::f64::consts::PI / 2.0) * scaling(2.0, 1.0, 3.0))
+            .unwrap();
+        let n = q.normal_at(Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(n, Vector3::new(0.0, 0.0, 1.0));
+    }
+}
@@ -1,32 +1,44 @@
-use super::{matrice::Matrice, tuple::Tuple};
+use super::{
+    matrice::Matrice4,
+    tuple::{Point3, Vector3},
+};
 
 #[derive(Debug)]
 pub struct Ray {
-    pub origin: Tuple,
-    pub direction: Tuple,
+    pub origin: Point3,
+    pub direction: Vector3,
+    // moment within the shutter interval the ray samples, for motion blur
+    pub time: f64,
 }
 impl Ray {
-    pub fn new(origin: Tuple, direction: Tuple) -> Ray {
-        Ray { origin, direction }
+    pub fn new(origin: Point3, direction: Vector3) -> Ray {
+        Self::with_time(origin, direction, 0.0)
     }
-    pub fn position(&self, t: f32) -> Tuple {
+    pub fn with_time(origin: Point3, direction: Vector3, time: f64) -> Ray {
+        Ray {
+            origin,
+            direction,
+            time,
+        }
+    }
+    pub fn position(&self, t: f64) -> Point3 {
         self.origin + self.direction * t
     }
 }
 
-pub fn transform(r: &Ray, m: Matrice) -> Ray {
-    Ray::new(m.clone() * r.origin, m.clone() * r.direction)
+pub fn transform(r: &Ray, m: &Matrice4) -> Ray {
+    Ray::with_time(m * r.origin, m * r.direction, r.time)
 }
 #[cfg(test)]
 mod ray_tests {
-    use crate::features::tuple::Tuple;
+    use crate::features::tuple::{Point3, Vector3};
 
     use super::Ray;
 
     #[test]
     fn test_creating_ray() {
-        let origin = Tuple::point(1.0, 2.0, 3.0);
-        let direction = Tuple::vector(4.0, 5.0, 6.0);
+        let origin = Point3::new(1.0, 2.0, 3.0);
+        let direction = Vector3::new(4.0, 5.0, 6.0);
         let ray = Ray::new(origin, direction);
 
         assert_eq!(origin, ray.origin);
@@ -35,14 +47,14 @@ mod ray_tests {
 
     #[test]
     fn test_ray_position() {
-        let origin = Tuple::point(2.0, 3.0, 4.0);
-        let direction = Tuple::vector(1.0, 0.0, 0.0);
+        let origin = Point3::new(2.0, 3.0, 4.0);
+        let direction = Vector3::new(1.0, 0.0, 0.0);
         let ray = Ray::new(origin, direction);
         println!("{:?}", ray.position(2.5));
         assert!(ray.position(0.0) == origin);
-        assert_eq!(ray.position(1.0), Tuple::point(3.0, 3.0, 4.0));
-        assert_eq!(ray.position(-1.0), Tuple::point(1.0, 3.0, 4.0));
-        assert_eq!(ray.position(2.5), Tuple::point(4.5, 3.0, 4.0));
+        assert_eq!(ray.position(1.0), Point3::new(3.0, 3.0, 4.0));
+        assert_eq!(ray.position(-1.0), Point3::new(1.0, 3.0, 4.0));
+        assert_eq!(ray.position(2.5), Point3::new(4.5, 3.0, 4.0));
     }
 }
 
@@ -50,26 +62,33 @@ mod ray_tests {
 mod ray_transformation_tests {
     use crate::features::{
         transformations::{scaling, translation},
-        tuple::Tuple,
+        tuple::{Point3, Vector3},
     };
 
     use super::{transform, Ray};
 
     #[test]
     fn test_translating_ray() {
-        let r = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0));
+        let r = Ray::new(Point3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 1.0, 0.0));
         let m = translation(3.0, 4.0, 5.0);
-        let r2 = transform(&r, m);
-        assert_eq!(r2.origin, Tuple::point(4.0, 6.0, 8.0));
-        assert_eq!(r2.direction, Tuple::vector(0.0, 1.0, 0.0));
+        let r2 = transform(&r, &m);
+        assert_eq!(r2.origin, Point3::new(4.0, 6.0, 8.0));
+        assert_eq!(r2.direction, Vector3::new(0.0, 1.0, 0.0));
     }
 
     #[test]
     fn test_scaling_ray() {
-        let r = Ray::new(Tuple::point(1.0, 2.0, 3.0), Tuple::vector(0.0, 1.0, 0.0));
+        let r = Ray::new(Point3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 1.0, 0.0));
         let m = scaling(2.0, 3.0, 4.0);
-        let r2 = transform(&r, m);
-        assert_eq!(r2.origin, Tuple::point(2.0, 6.0, 12.0));
-        assert_eq!(r2.direction, Tuple::vector(0.0, 3.0, 0.0));
+        let r2 = transform(&r, &m);
+        assert_eq!(r2.origin, Point3::new(2.0, 6.0, 12.0));
+        assert_eq!(r2.direction, Vector3::new(0.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn test_transforming_keeps_time() {
+        let r = Ray::with_time(Point3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 1.0, 0.0), 0.4);
+        let r2 = transform(&r, &translation(3.0, 4.0, 5.0));
+        assert_eq!(r2.time, 0.4);
     }
 }
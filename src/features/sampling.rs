@@ -0,0 +1,133 @@
+use std::f64::consts::PI;
+
+use super::tuple::Vector3;
+
+// two unit vectors that together with `normal` form an orthonormal basis
+pub fn orthonormal_basis(normal: &Vector3) -> (Vector3, Vector3) {
+    let helper = if normal.x.abs() > 0.9 {
+        Vector3::new(0.0, 1.0, 0.0)
+    } else {
+        Vector3::new(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(normal).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent, bitangent)
+}
+
+// maps two uniform numbers in [0, 1) to a direction around `normal` with a
+// density proportional to the cosine of its angle to the normal
+pub fn cosine_hemisphere(normal: &Vector3, u1: f64, u2: f64) -> Vector3 {
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    let phi = 2.0 * PI * u1;
+    let r = u2.sqrt();
+    let z = (1.0 - u2).max(0.0).sqrt();
+    (tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + *normal * z).normalize()
+}
+
+// maps two uniform numbers in [0, 1) to a direction in a Phong lobe around
+// `axis`; the lobe narrows as `blur` goes to 0, where it is `axis` itself
+pub fn glossy_lobe(axis: &Vector3, blur: f64, u1: f64, u2: f64) -> Vector3 {
+    if blur <= 0.0 {
+        return *axis;
+    }
+    let exponent = (2.0 / (blur * blur) - 2.0).max(0.0);
+    let (tangent, bitangent) = orthonormal_basis(axis);
+    let phi = 2.0 * PI * u1;
+    let z = u2.powf(1.0 / (exponent + 1.0));
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    (tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + *axis * z).normalize()
+}
+
+// `direction` mirrored across the surface if it points against `normal`, so
+// lobe samples that dip below a surface still leave on the right side
+pub fn fold_above(direction: &Vector3, normal: &Vector3) -> Vector3 {
+    let d = direction.dot(normal);
+    if d >= 0.0 {
+        return *direction;
+    }
+    *direction - *normal * (2.0 * d)
+}
+
+// uniformly distributed point on the unit disk, as (x, y)
+pub fn uniform_disk(u1: f64, u2: f64) -> (f64, f64) {
+    let r = u1.sqrt();
+    let theta = 2.0 * PI * u2;
+    (r * theta.cos(), r * theta.sin())
+}
+
+// the SplitMix64 finalizer, which spreads nearby inputs far apart
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod sampling_tests {
+    use super::*;
+
+    #[test]
+    fn basis_is_orthonormal() {
+        let n = Vector3::new(1.0, 2.0, 3.0).normalize();
+        let (t, b) = orthonormal_basis(&n);
+        assert!(t.dot(&n).abs() < 0.00001);
+        assert!(b.dot(&n).abs() < 0.00001);
+        assert!(t.dot(&b).abs() < 0.00001);
+        assert!((t.magnitude() - 1.0).abs() < 0.00001);
+        assert!((b.magnitude() - 1.0).abs() < 0.00001);
+    }
+
+    #[test]
+    fn hemisphere_samples_stay_above_surface() {
+        let n = Vector3::new(0.0, 1.0, 0.0);
+        for i in 0..16 {
+            for j in 0..16 {
+                let d = cosine_hemisphere(&n, i as f64 / 16.0, j as f64 / 16.0);
+                assert!(d.dot(&n) >= 0.0);
+                assert!((d.magnitude() - 1.0).abs() < 0.0001);
+            }
+        }
+        assert_eq!(cosine_hemisphere(&n, 0.3, 0.0), n);
+    }
+
+    #[test]
+    fn glossy_lobe_narrows_with_roughness() {
+        let axis = Vector3::new(0.0, 0.0, 1.0);
+        assert_eq!(glossy_lobe(&axis, 0.0, 0.3, 0.7), axis);
+        let spread = |blur: f64| {
+            let mut lowest = 1.0_f64;
+            for i in 0..16 {
+                for j in 0..16 {
+                    let d = glossy_lobe(&axis, blur, i as f64 / 16.0, (j as f64 + 0.5) / 16.0);
+                    assert!((d.magnitude() - 1.0).abs() < 0.0001);
+                    lowest = lowest.min(d.dot(&axis));
+                }
+            }
+            lowest
+        };
+        assert!(spread(0.1) > spread(0.5));
+        assert!(spread(0.1) > 0.9);
+    }
+
+    #[test]
+    fn folded_directions_stay_above_surface() {
+        let n = Vector3::new(0.0, 1.0, 0.0);
+        let above = Vector3::new(1.0, 1.0, 0.0);
+        assert_eq!(fold_above(&above, &n), above);
+        assert_eq!(
+            fold_above(&Vector3::new(1.0, -1.0, 0.0), &n),
+            Vector3::new(1.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn disk_samples_stay_inside_unit_disk() {
+        for i in 0..16 {
+            for j in 0..16 {
+                let (x, y) = uniform_disk(i as f64 / 16.0, j as f64 / 16.0);
+                assert!(x * x + y * y <= 1.0001);
+            }
+        }
+    }
+}
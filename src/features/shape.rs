@@ -1,75 +1,217 @@
+use std::{any::Any, borrow::Cow, fmt::Debug};
+
 use super::{
     cube::Cube,
-    intersections::Intersection,
+    error::Result,
+    intersections::{intersections, Intersection},
     materials::Material,
-    matrice::Matrice,
+    matrice::Matrice4,
+    motion::Motion,
     planes::Plane,
+    quads::Quad,
     rays::{transform, Ray},
     spheres::Sphere,
-    tuple::Tuple,
+    transformations::Transform,
+    tuple::{Point3, Vector3},
 };
 
+// What every shape shares. An implementor describes its geometry in object
+// space and hands out its transform, material and motion; moving rays and
+// normals between world and object space is done here, the same way for
+// every shape. Types outside this crate implement it and are wrapped in
+// `Shape::Custom`.
+pub trait Primitive: DynPrimitive + Debug {
+    // the t of every crossing of an object-space ray, in any order
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64>;
+    // the object-space normal at an object-space point on the surface
+    fn local_normal_at(&self, point: Point3) -> Vector3;
+
+    fn transformation(&self) -> &Transform;
+    fn transformation_mut(&mut self) -> &mut Transform;
+    fn material(&self) -> &Material;
+    fn material_mut(&mut self) -> &mut Material;
+    fn motion(&self) -> Option<&Motion>;
+    fn motion_mut(&mut self) -> &mut Option<Motion>;
+
+    // an object-space point drawn uniformly over the surface from two numbers
+    // in [0, 1), its normal and the surface area. Shapes that cannot be
+    // sampled, like infinite ones, return None and cannot act as lights.
+    fn local_sample(&self, _u: f64, _v: f64) -> Option<(Point3, Vector3, f64)> {
+        None
+    }
+    // texture coordinates in [0, 1) for an object-space point, a planar
+    // projection along y unless the shape knows better
+    fn local_uv(&self, point: Point3) -> (f64, f64) {
+        (point.x - point.x.floor(), point.z - point.z.floor())
+    }
+
+    fn transform(&self) -> &Matrice4 {
+        self.transformation().matrix()
+    }
+    fn set_transform(&mut self, t: Matrice4) -> Result<()> {
+        *self.transformation_mut() = Transform::new(t)?;
+        Ok(())
+    }
+    fn set_material(&mut self, m: Material) {
+        *self.material_mut() = m
+    }
+    fn normal_at(&self, world_point: Point3) -> Vector3 {
+        let transformation = self.transformation();
+        let object_point = transformation.inverse() * world_point;
+        let object_normal = self.local_normal_at(object_point);
+        (transformation.inverse_transpose() * object_normal).normalize()
+    }
+}
+
+// Cloning and comparing boxed primitives, implemented for every primitive
+// that is `Clone + PartialEq`, so a custom shape only has to derive them.
+pub trait DynPrimitive {
+    fn clone_box(&self) -> Box<dyn Primitive>;
+    fn as_any(&self) -> &dyn Any;
+    fn eq_dyn(&self, other: &dyn Any) -> bool;
+}
+
+impl<T: Primitive + Clone + PartialEq + 'static> DynPrimitive for T {
+    fn clone_box(&self) -> Box<dyn Primitive> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn eq_dyn(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<T>() == Some(self)
+    }
+}
+
+impl Clone for Box<dyn Primitive> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for Box<dyn Primitive> {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_dyn(other.as_any())
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum Shape {
     Sphere(Sphere),
     Plane(Plane),
     Cube(Cube),
+    Quad(Quad),
+    Custom(Box<dyn Primitive>),
 }
 
 impl Shape {
-    pub fn intersect(&self, r: &Ray) -> Vec<Intersection> {
-        let ray = transform(r, self.transform().inverse().unwrap());
-        match self {
-            Shape::Sphere(s) => s.intersect(&ray),
-            Shape::Plane(p) => p.intersect(&ray),
-            Shape::Cube(c) => c.intersect(&ray),
-        }
+    pub fn custom(primitive: impl Primitive + 'static) -> Self {
+        Shape::Custom(Box::new(primitive))
     }
-    pub fn set_transform(&mut self, t: Matrice) {
+    pub fn primitive(&self) -> &dyn Primitive {
         match self {
-            Shape::Sphere(s) => s.set_transform(t),
-            Shape::Plane(p) => p.set_transform(t),
-            Shape::Cube(c) => c.transform = t,
+            Shape::Sphere(s) => s,
+            Shape::Plane(p) => p,
+            Shape::Cube(c) => c,
+            Shape::Quad(q) => q,
+            Shape::Custom(p) => p.as_ref(),
         }
     }
-    pub fn transform(&self) -> Matrice {
+    pub fn primitive_mut(&mut self) -> &mut dyn Primitive {
         match self {
-            Shape::Sphere(s) => s.transform.clone(),
-            Shape::Plane(p) => p.transform(),
-            Shape::Cube(c) => c.transform.clone(),
+            Shape::Sphere(s) => s,
+            Shape::Plane(p) => p,
+            Shape::Cube(c) => c,
+            Shape::Quad(q) => q,
+            Shape::Custom(p) => p.as_mut(),
         }
     }
-    pub fn normal_at(&self, point: Tuple) -> Tuple {
-        match self {
-            Shape::Sphere(s) => s.normal_at(point),
-            Shape::Plane(p) => p.normal_at(point),
-            Shape::Cube(c) => c.normal_at(point),
-        }
+    pub fn intersect(&self, r: &Ray) -> Vec<Intersection<'_>> {
+        // a motion can pass through a singular pose, where the shape is
+        // squashed flat and has nothing to hit
+        let Ok(posed) = self.at_time(r.time) else {
+            return vec![];
+        };
+        let ray = transform(r, posed.inverse());
+        let mut xs: Vec<Intersection> = posed
+            .primitive()
+            .local_intersect(&ray)
+            .into_iter()
+            .map(|t| Intersection::new(t, self))
+            .collect();
+        intersections(&mut xs)
+    }
+    // the nearest crossing with t strictly between `t_min` and `t_max`,
+    // without building or sorting intersections
+    pub fn closest_t(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<f64> {
+        let posed = self.at_time(r.time).ok()?;
+        let ray = transform(r, posed.inverse());
+        posed
+            .primitive()
+            .local_intersect(&ray)
+            .into_iter()
+            .filter(|t| *t > t_min && *t < t_max)
+            .min_by(f64::total_cmp)
+    }
+    // a moving shape frozen at `time`; shapes that do not move are borrowed
+    pub fn at_time(&self, time: f64) -> Result<Cow<'_, Shape>> {
+        let Some(motion) = self.primitive().motion() else {
+            return Ok(Cow::Borrowed(self));
+        };
+        let mut posed = self.clone();
+        posed.set_transform(motion.at(time))?;
+        posed.set_motion(None);
+        Ok(Cow::Owned(posed))
+    }
+    pub fn set_transform(&mut self, t: Matrice4) -> Result<()> {
+        self.primitive_mut().set_transform(t)
+    }
+    pub fn transformation(&self) -> &Transform {
+        self.primitive().transformation()
+    }
+    pub fn transform(&self) -> &Matrice4 {
+        self.transformation().matrix()
+    }
+    pub fn inverse(&self) -> &Matrice4 {
+        self.transformation().inverse()
+    }
+    pub fn inverse_transpose(&self) -> &Matrice4 {
+        self.transformation().inverse_transpose()
+    }
+    pub fn motion(&self) -> Option<Motion> {
+        self.primitive().motion().cloned()
+    }
+    pub fn set_motion(&mut self, motion: Option<Motion>) {
+        *self.primitive_mut().motion_mut() = motion
+    }
+    pub fn normal_at(&self, point: Point3) -> Vector3 {
+        self.primitive().normal_at(point)
     }
     pub fn material(&self) -> Material {
-        match self {
-            Shape::Sphere(s) => s.material.clone(),
-            Shape::Plane(p) => p.material.clone(),
-            Shape::Cube(c) => c.material.clone(),
-        }
+        self.primitive().material().clone()
     }
     pub fn set_material(&mut self, m: Material) {
-        match self {
-            Shape::Sphere(s) => s.material = m,
-            Shape::Plane(_) => {}
-            Shape::Cube(_) => {}
-        }
+        self.primitive_mut().set_material(m)
     }
-    pub fn set_material_ambient(&mut self, ambient: f32) {
-        if let Shape::Sphere(s) = self {
-            s.material.ambient = ambient
-        }
+    // a point drawn uniformly over the surface from two numbers in [0, 1),
+    // its world normal and the world-space area one sample stands for
+    pub fn sample_surface(&self, u: f64, v: f64) -> Option<(Point3, Vector3, f64)> {
+        let (point, normal, area) = self.primitive().local_sample(u, v)?;
+        let transform = *self.transform();
+        let world_normal = self.inverse_transpose() * normal;
+        // Nanson's formula for how the transform stretches a patch of surface;
+        // an affine matrix has the determinant of its linear part
+        let stretch = transform.determinant().abs() * world_normal.magnitude();
+        Some((transform * point, world_normal.normalize(), area * stretch))
+    }
+    pub fn set_material_ambient(&mut self, ambient: f64) {
+        self.primitive_mut().material_mut().ambient = ambient
     }
 }
 
 #[cfg(test)]
 mod shape_tests {
-    use std::f32::consts::PI;
+    use std::f64::consts::PI;
 
     use crate::features::transformations::{rotation_z, scaling, translation};
 
@@ -82,14 +224,38 @@ mod shape_tests {
     #[test]
     fn default_transformation() {
         let s = test_shape();
-        assert_eq!(s.transform(), Matrice::identity_matrix(4))
+        assert_eq!(s.transform(), &Matrice4::identity())
     }
 
     #[test]
     fn assigning_transformation() {
         let mut s = test_shape();
-        s.set_transform(translation(2.0, 3.0, 4.0));
-        assert_eq!(s.transform(), translation(2.0, 3.0, 4.0))
+        s.set_transform(translation(2.0, 3.0, 4.0)).unwrap();
+        assert_eq!(s.transform(), &translation(2.0, 3.0, 4.0))
+    }
+
+    #[test]
+    fn singular_transformation_is_rejected_and_leaves_the_shape_alone() {
+        let mut s = test_shape();
+        s.set_transform(translation(2.0, 3.0, 4.0)).unwrap();
+        assert!(s.set_transform(scaling(0.0, 1.0, 1.0)).is_err());
+        assert_eq!(s.transform(), &translation(2.0, 3.0, 4.0))
+    }
+
+    #[test]
+    fn shape_squashed_flat_by_its_motion_is_not_hit() {
+        let mut s = test_shape();
+        s.set_motion(Some(Motion::linear(
+            scaling(1.0, 1.0, 1.0),
+            scaling(-1.0, 1.0, 1.0),
+        )));
+        let r = Ray::with_time(
+            Point3::new(0.0, 0.0, -5.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0.5,
+        );
+        assert!(s.at_time(0.5).is_err());
+        assert!(s.intersect(&r).is_empty());
     }
 
     #[test]
@@ -110,14 +276,14 @@ mod shape_tests {
     #[test]
     fn computing_normal_on_translated_shape() {
         let mut s = test_shape();
-        s.set_transform(translation(0.0, 1.0, 0.0));
-        let n = s.normal_at(Tuple::point(0.0, 1.70711, -std::f32::consts::FRAC_1_SQRT_2));
+        s.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        let n = s.normal_at(Point3::new(0.0, 1.70711, -std::f64::consts::FRAC_1_SQRT_2));
         assert_eq!(
             n,
-            Tuple::vector(
+            Vector3::new(
                 0.0,
-                std::f32::consts::FRAC_1_SQRT_2,
-                -std::f32::consts::FRAC_1_SQRT_2
+                std::f64::consts::FRAC_1_SQRT_2,
+                -std::f64::consts::FRAC_1_SQRT_2
             )
         )
     }
@@ -125,12 +291,194 @@ mod shape_tests {
     #[test]
     fn computing_normal_on_transformed_shape() {
         let mut s = test_shape();
-        s.set_transform(scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0));
-        let n = s.normal_at(Tuple::point(
+        s.set_transform(scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0))
+            .unwrap();
+        let n = s.normal_at(Point3::new(
             0.0,
-            2.0_f32.sqrt() / 2.0,
-            -(2.0_f32.sqrt() / 2.0),
+            2.0_f64.sqrt() / 2.0,
+            -(2.0_f64.sqrt() / 2.0),
         ));
-        assert_eq!(n, Tuple::vector(0.0, 0.97014, -0.24254))
+        assert_eq!(n, Vector3::new(0.0, 0.97014, -0.24254))
+    }
+
+    #[test]
+    fn sampling_scaled_sphere_surface() {
+        let mut s = test_shape();
+        s.set_transform(translation(1.0, 0.0, 0.0) * scaling(2.0, 2.0, 2.0))
+            .unwrap();
+        let (point, normal, area) = s.sample_surface(0.3, 0.7).unwrap();
+        assert!(((point - Point3::new(1.0, 0.0, 0.0)).magnitude() - 2.0).abs() < 0.0001);
+        assert_eq!(normal, (point - Point3::new(1.0, 0.0, 0.0)).normalize());
+        assert!((area - 16.0 * PI).abs() < 0.001);
+    }
+
+    #[test]
+    fn sampling_stretched_quad_and_cube() {
+        let mut q = Shape::Quad(Quad::new());
+        q.set_transform(scaling(2.0, 5.0, 3.0)).unwrap();
+        let (point, normal, area) = q.sample_surface(1.0, 0.0).unwrap();
+        assert_eq!(point, Point3::new(2.0, 0.0, -3.0));
+        assert_eq!(normal, Vector3::new(0.0, 1.0, 0.0));
+        assert!((area - 24.0).abs() < 0.0001);
+
+        let c = Shape::Cube(Cube::new());
+        let (point, normal, area) = c.sample_surface(0.5 / 6.0, 0.5).unwrap();
+        assert_eq!(point, Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(normal, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(area, 24.0);
+        let (point, normal, _) = c.sample_surface(3.5 / 6.0, 0.5).unwrap();
+        assert_eq!(point, Point3::new(0.0, -1.0, 0.0));
+        assert_eq!(normal, Vector3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn infinite_plane_cannot_be_sampled() {
+        let p = Shape::Plane(Plane::new());
+        assert!(p.sample_surface(0.5, 0.5).is_none());
+    }
+
+    #[test]
+    fn moving_shape_is_hit_where_it_is_at_ray_time() {
+        let mut s = test_shape();
+        s.set_motion(Some(Motion::linear(
+            translation(0.0, 0.0, 0.0),
+            translation(0.0, 4.0, 0.0),
+        )));
+        let at_start = Ray::with_time(
+            Point3::new(0.0, 0.0, -5.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0.0,
+        );
+        let at_end = Ray::with_time(
+            Point3::new(0.0, 0.0, -5.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            1.0,
+        );
+        let halfway = Ray::with_time(
+            Point3::new(0.0, 2.0, -5.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0.5,
+        );
+        assert_eq!(s.intersect(&at_start).len(), 2);
+        assert_eq!(s.intersect(&at_end).len(), 0);
+        let xs = s.intersect(&halfway);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert!(xs[0].is_on(&s));
+        let posed = xs[0].object.at_time(halfway.time).unwrap();
+        assert_eq!(posed.transform(), &translation(0.0, 2.0, 0.0));
+        assert_eq!(
+            posed.normal_at(Point3::new(0.0, 2.0, -1.0)),
+            Vector3::new(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn every_shape_takes_a_material() {
+        let mut m = Material::new();
+        m.ambient = 0.7;
+        for mut s in [
+            test_shape(),
+            Shape::Plane(Plane::new()),
+            Shape::Cube(Cube::new()),
+            Shape::Quad(Quad::new()),
+        ] {
+            s.set_material(m.clone());
+            assert_eq!(s.material(), m);
+            s.set_material_ambient(0.2);
+            assert_eq!(s.material().ambient, 0.2);
+        }
+    }
+
+    // the unit disc in the xz plane, as a crate user would write it
+    #[derive(Clone, PartialEq, Debug)]
+    struct Disc {
+        transform: Transform,
+        material: Material,
+        motion: Option<Motion>,
+    }
+
+    impl Primitive for Disc {
+        fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+            if ray.direction.y == 0.0 {
+                return vec![];
+            }
+            let t = -ray.origin.y / ray.direction.y;
+            let p = ray.position(t);
+            if p.x * p.x + p.z * p.z > 1.0 {
+                return vec![];
+            }
+            vec![t]
+        }
+        fn local_normal_at(&self, _point: Point3) -> Vector3 {
+            Vector3::new(0.0, 1.0, 0.0)
+        }
+        fn transformation(&self) -> &Transform {
+            &self.transform
+        }
+        fn transformation_mut(&mut self) -> &mut Transform {
+            &mut self.transform
+        }
+        fn material(&self) -> &Material {
+            &self.material
+        }
+        fn material_mut(&mut self) -> &mut Material {
+            &mut self.material
+        }
+        fn motion(&self) -> Option<&Motion> {
+            self.motion.as_ref()
+        }
+        fn motion_mut(&mut self) -> &mut Option<Motion> {
+            &mut self.motion
+        }
+    }
+
+    fn disc() -> Shape {
+        Shape::custom(Disc {
+            transform: Transform::default(),
+            material: Material::new(),
+            motion: None,
+        })
+    }
+
+    #[test]
+    fn custom_primitive_is_transformed_like_the_others() {
+        let mut s = disc();
+        s.set_transform(translation(0.0, 2.0, 0.0) * scaling(2.0, 2.0, 2.0))
+            .unwrap();
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        let xs = s.intersect(&Ray::new(Point3::new(1.5, 5.0, 0.0), down));
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 3.0);
+        assert!(xs[0].is_on(&s));
+        assert!(s
+            .intersect(&Ray::new(Point3::new(2.5, 5.0, 0.0), down))
+            .is_empty());
+        assert_eq!(
+            s.normal_at(Point3::new(1.0, 2.0, 0.0)),
+            Vector3::new(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn custom_primitive_shares_material_and_motion_handling() {
+        let mut s = disc();
+        let mut m = Material::new();
+        m.reflective = 0.5;
+        s.set_material(m.clone());
+        assert_eq!(s.material(), m);
+        assert_ne!(s, disc());
+        assert!(s.sample_surface(0.5, 0.5).is_none());
+
+        s.set_motion(Some(Motion::linear(
+            translation(0.0, 0.0, 0.0),
+            translation(0.0, 4.0, 0.0),
+        )));
+        let r = Ray::with_time(
+            Point3::new(0.0, 5.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            0.5,
+        );
+        assert_eq!(s.intersect(&r)[0].t, 3.0);
     }
 }
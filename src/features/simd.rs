@@ -0,0 +1,131 @@
+use std::ops::{Add, Div, Index, Mul, Neg, Sub};
+
+// N lanes of f64 handled in lockstep. Every operation is a plain loop over a
+// fixed-size, aligned array, which LLVM turns into SSE/AVX/NEON instructions
+// on stable Rust without any target-specific code.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(C, align(32))]
+pub struct Simd<const N: usize>(pub [f64; N]);
+
+pub type F64x4 = Simd<4>;
+pub type F64x8 = Simd<8>;
+
+impl<const N: usize> Simd<N> {
+    pub fn splat(value: f64) -> Self {
+        Self([value; N])
+    }
+    pub fn from_fn(f: impl FnMut(usize) -> f64) -> Self {
+        Self(std::array::from_fn(f))
+    }
+    pub fn to_array(self) -> [f64; N] {
+        self.0
+    }
+    fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::from_fn(|i| f(self.0[i]))
+    }
+    fn zip(self, rhs: Self, f: impl Fn(f64, f64) -> f64) -> Self {
+        Self::from_fn(|i| f(self.0[i], rhs.0[i]))
+    }
+
+    pub fn sqrt(self) -> Self {
+        self.map(f64::sqrt)
+    }
+    pub fn abs(self) -> Self {
+        self.map(f64::abs)
+    }
+    pub fn min(self, rhs: Self) -> Self {
+        self.zip(rhs, f64::min)
+    }
+    pub fn max(self, rhs: Self) -> Self {
+        self.zip(rhs, f64::max)
+    }
+    pub fn sum(self) -> f64 {
+        self.0.iter().sum()
+    }
+
+    pub fn lt(self, rhs: Self) -> [bool; N] {
+        std::array::from_fn(|i| self.0[i] < rhs.0[i])
+    }
+    pub fn le(self, rhs: Self) -> [bool; N] {
+        std::array::from_fn(|i| self.0[i] <= rhs.0[i])
+    }
+    // lanes of `a` where the mask is set, of `b` elsewhere
+    pub fn select(mask: [bool; N], a: Self, b: Self) -> Self {
+        Self::from_fn(|i| if mask[i] { a.0[i] } else { b.0[i] })
+    }
+}
+
+impl<const N: usize> Index<usize> for Simd<N> {
+    type Output = f64;
+    fn index(&self, lane: usize) -> &f64 {
+        &self.0[lane]
+    }
+}
+
+impl<const N: usize> Add for Simd<N> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        self.zip(rhs, |a, b| a + b)
+    }
+}
+
+impl<const N: usize> Sub for Simd<N> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self.zip(rhs, |a, b| a - b)
+    }
+}
+
+impl<const N: usize> Mul for Simd<N> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        self.zip(rhs, |a, b| a * b)
+    }
+}
+
+impl<const N: usize> Mul<f64> for Simd<N> {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        self.map(|a| a * rhs)
+    }
+}
+
+impl<const N: usize> Div for Simd<N> {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        self.zip(rhs, |a, b| a / b)
+    }
+}
+
+impl<const N: usize> Neg for Simd<N> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.map(|a| -a)
+    }
+}
+
+#[cfg(test)]
+mod simd_tests {
+    use super::*;
+
+    #[test]
+    fn lanewise_arithmetic() {
+        let a = F64x4::from_fn(|i| i as f64);
+        let b = F64x4::splat(2.0);
+        assert_eq!((a + b).to_array(), [2.0, 3.0, 4.0, 5.0]);
+        assert_eq!((a * b - b).to_array(), [-2.0, 0.0, 2.0, 4.0]);
+        assert_eq!((a / b).sqrt()[2], 1.0);
+        assert_eq!((a * b).sum(), 12.0);
+    }
+
+    #[test]
+    fn masks_and_select() {
+        let a = F64x8::from_fn(|i| i as f64 - 4.0);
+        let zero = F64x8::splat(0.0);
+        let mask = a.lt(zero);
+        assert_eq!(mask, [true, true, true, true, false, false, false, false]);
+        let clamped = Simd::select(mask, zero, a);
+        assert_eq!(clamped.to_array(), [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(a.abs().max(F64x8::splat(3.5))[0], 4.0);
+    }
+}
@@ -0,0 +1,218 @@
+use std::f64::consts::PI;
+
+use super::{
+    background::equirectangular_direction,
+    canvas::Canvas,
+    environment::Environment,
+    lights::Sun,
+    tuple::{Color, Vector3},
+};
+
+// The Preetham daylight sky: clear-sky radiance for any direction from the
+// sun's position and the turbidity, i.e. how hazy the air is (2 is very
+// clear, 10 is hazy). The sun itself is not in the picture; it comes as a
+// matching `Sun` light.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Sky {
+    sun_direction: Vector3,
+    turbidity: f64,
+    // Perez coefficients A to E for luminance and the two chromaticities
+    perez: [[f64; 5]; 3],
+    // luminance and chromaticity straight up
+    zenith: [f64; 3],
+    // the same function at the zenith, which the zenith values are relative to
+    zenith_perez: [f64; 3],
+    // from the model's kcd/m^2 to scene radiance
+    pub intensity: f64,
+    // radiance of the sun light at the zenith, before the atmosphere dims it
+    pub sun_intensity: f64,
+}
+
+impl Sky {
+    // `elevation` above the horizon and `azimuth` around it, both in radians;
+    // azimuth 0 looks down -z and PI / 2 down +x
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let sun_direction = Vector3::new(
+            azimuth.sin() * elevation.cos(),
+            elevation.sin(),
+            -azimuth.cos() * elevation.cos(),
+        );
+        let t = turbidity;
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+        let theta_s = PI / 2.0 - elevation.clamp(0.0, PI / 2.0);
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let chromaticity = |m: [[f64; 4]; 3]| {
+            let thetas = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+            let ts = [t * t, t, 1.0];
+            (0..3)
+                .map(|row| ts[row] * (0..4).map(|col| m[row][col] * thetas[col]).sum::<f64>())
+                .sum::<f64>()
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+        let zenith_perez = std::array::from_fn(|ix| perez_function(&perez[ix], 1.0, theta_s));
+        Self {
+            sun_direction,
+            turbidity,
+            perez,
+            zenith: [zenith_luminance.max(0.0), zenith_x, zenith_y],
+            zenith_perez,
+            intensity: 0.05,
+            sun_intensity: 5.0,
+        }
+    }
+
+    pub fn sun_direction(&self) -> Vector3 {
+        self.sun_direction
+    }
+
+    pub fn turbidity(&self) -> f64 {
+        self.turbidity
+    }
+
+    // the sky radiance looking along `direction`; below the horizon the
+    // horizon carries on
+    pub fn at(&self, direction: &Vector3) -> Color {
+        let d = direction.normalize();
+        let cos_theta = d.y.max(0.001);
+        let gamma = d.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y]: [f64; 3] = std::array::from_fn(|ix| {
+            self.zenith[ix] * perez_function(&self.perez[ix], cos_theta, gamma)
+                / self.zenith_perez[ix]
+        });
+        xyy_to_rgb(luminance * self.intensity, x, y)
+    }
+
+    // sunlight as it reaches the ground, dimmed and reddened by the air
+    // it passes through on the way
+    pub fn sun(&self) -> Sun {
+        let elevation = self.sun_direction.y.clamp(0.0, 1.0).asin().to_degrees();
+        // Kasten and Young's relative air mass
+        let air_mass =
+            1.0 / (elevation.to_radians().sin() + 0.50572 * (elevation + 6.07995).powf(-1.6364));
+        // aerosols after Angstrom, with the turbidity relation from Preetham
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |wavelength_um: f64| {
+            let rayleigh = 0.008735 * wavelength_um.powf(-4.08);
+            let aerosol = beta * wavelength_um.powf(-1.3);
+            (-air_mass * (rayleigh + aerosol)).exp()
+        };
+        let color = Color::new(
+            transmittance(0.68),
+            transmittance(0.55),
+            transmittance(0.44),
+        );
+        Sun::new(self.sun_direction, color * self.sun_intensity)
+    }
+
+    // the sky baked into an equirectangular image, for lighting a scene
+    pub fn environment(&self, width: usize, height: usize) -> Environment {
+        let mut image = Canvas::new(width, height);
+        for (y, row) in image.canvas.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let u = (x as f64 + 0.5) / width as f64;
+                let v = (y as f64 + 0.5) / height as f64;
+                *pixel = self.at(&equirectangular_direction(u, v));
+            }
+        }
+        Environment::new(image)
+    }
+}
+
+// Perez et al.'s sky distribution at zenith angle theta and angle gamma
+// from the sun
+fn perez_function(c: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    (1.0 + c[0] * (c[1] / cos_theta).exp())
+        * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos().powi(2))
+}
+
+// CIE xyY to linear sRGB
+fn xyy_to_rgb(luminance: f64, x: f64, y: f64) -> Color {
+    if y <= 0.0 {
+        return Color::black();
+    }
+    let big_x = x * luminance / y;
+    let big_z = (1.0 - x - y) * luminance / y;
+    Color::new(
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    )
+}
+
+#[cfg(test)]
+mod sky_tests {
+    use super::*;
+
+    #[test]
+    fn zenith_has_the_zenith_luminance() {
+        let sky = Sky::new(PI / 4.0, 0.0, 3.0);
+        let up = sky.at(&Vector3::new(0.0, 1.0, 0.0));
+        // the zenith luminance formula at T = 3 and a sun 45 degrees up
+        let chi = (4.0 / 9.0 - 3.0 / 120.0) * (PI / 2.0);
+        let expected = (4.0453 * 3.0 - 4.9710) * chi.tan() - 0.2155 * 3.0 + 2.4192;
+        assert!((up.luminance() - expected * sky.intensity).abs() < 1e-3);
+        // and a clear sky is blue overhead
+        assert!(up.b > up.r);
+    }
+
+    #[test]
+    fn sky_is_brightest_around_the_sun() {
+        let sky = Sky::new(PI / 6.0, PI / 2.0, 2.5);
+        let near = sky.at(&Vector3::new(1.0, 0.6, 0.05));
+        let away = sky.at(&Vector3::new(-1.0, 0.6, 0.0));
+        assert!(near.luminance() > 2.0 * away.luminance());
+        assert_eq!(sky.sun_direction(), Vector3::new(0.866025, 0.5, 0.0));
+    }
+
+    #[test]
+    fn low_and_hazy_suns_are_dimmer_and_redder() {
+        let noon = Sky::new(PI / 2.0 - 0.1, 0.0, 2.0).sun();
+        let evening = Sky::new(0.1, 0.0, 2.0).sun();
+        let hazy = Sky::new(PI / 2.0 - 0.1, 0.0, 8.0).sun();
+        assert!(evening.intensity.luminance() < noon.intensity.luminance());
+        assert!(hazy.intensity.luminance() < noon.intensity.luminance());
+        let redness = |c: Color| c.r / c.b;
+        assert!(redness(evening.intensity) > redness(noon.intensity));
+        assert!(noon.intensity.r < 5.0);
+    }
+
+    #[test]
+    fn baked_environment_matches_the_sky() {
+        let sky = Sky::new(PI / 3.0, 1.0, 3.0);
+        let environment = sky.environment(32, 16);
+        let d = equirectangular_direction((3.5) / 32.0, 5.5 / 16.0);
+        assert_eq!(environment.radiance(&d), sky.at(&d));
+    }
+}
@@ -1,68 +1,91 @@
-use uuid::Uuid;
+use std::f64::consts::PI;
 
 use super::{
-    intersections::{self, intersections, Intersection},
     materials::Material,
-    matrice::Matrice,
-    rays::{transform, Ray},
-    shape::Shape,
-    tuple::{Tuple, TupleType},
+    motion::Motion,
+    rays::Ray,
+    shape::Primitive,
+    transformations::Transform,
+    tuple::{Point3, Vector3},
 };
 
 #[derive(PartialEq, Debug, Clone, Eq)]
 pub struct Sphere {
-    pub id: Uuid,
-    pub transform: Matrice,
+    transform: Transform,
+    pub motion: Option<Motion>,
     pub material: Material,
 }
 
+impl Default for Sphere {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Sphere {
     pub fn new() -> Self {
         Self {
             material: Material::new(),
-            id: Uuid::new_v4(),
-            transform: Matrice::identity_matrix(4),
+            transform: Transform::default(),
+            motion: None,
         }
     }
     pub fn glass_sphere() -> Self {
         let mut s = Sphere::new();
-        s.transform = Matrice::identity_matrix(4);
         s.material.transparency = 1.0;
         s.material.refractive_index = 1.5;
         s
     }
 }
-impl Sphere {
-    pub fn set_transform(&mut self, transformation: Matrice) {
-        self.transform = transformation;
-    }
-    pub fn normal_at(&self, world_point: Tuple) -> Tuple {
-        let object_point = self.transform.inverse().unwrap() * world_point;
-        let object_normal = object_point - Tuple::point(0.0, 0.0, 0.0);
-        let mut world_normal = self.transform.inverse().unwrap().transpose() * object_normal;
-        world_normal.w = TupleType::Vector;
-        world_normal.normalize()
-    }
-    pub fn intersect(&self, r2: &Ray) -> Vec<Intersection> {
-        // let r2 = transform(r.clone(), self.transform.inverse().unwrap());
-        let sphere_to_ray = r2.origin - Tuple::point(0.0, 0.0, 0.0);
-        let a = r2.direction.dot(&r2.direction);
-        let b = 2.0 * r2.direction.dot(&sphere_to_ray);
+impl Primitive for Sphere {
+    fn local_intersect(&self, ray: &Ray) -> Vec<f64> {
+        let sphere_to_ray = ray.origin - Point3::ORIGIN;
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2.0 * ray.direction.dot(&sphere_to_ray);
         let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
         let disrciminant = b * b - 4.0 * a * c;
         if disrciminant < 0.0 {
             return vec![];
         }
-        intersections(&mut [
-            Intersection::new(
-                (-b - disrciminant.sqrt()) / (2.0 * a),
-                Shape::Sphere(self.clone()),
-            ),
-            Intersection::new(
-                (-b + disrciminant.sqrt()) / (2.0 * a),
-                Shape::Sphere(self.clone()),
-            ),
-        ])
+        vec![
+            (-b - disrciminant.sqrt()) / (2.0 * a),
+            (-b + disrciminant.sqrt()) / (2.0 * a),
+        ]
+    }
+    fn local_normal_at(&self, point: Point3) -> Vector3 {
+        point - Point3::ORIGIN
+    }
+    fn transformation(&self) -> &Transform {
+        &self.transform
+    }
+    fn transformation_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+    fn material(&self) -> &Material {
+        &self.material
+    }
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+    fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
+    fn motion_mut(&mut self) -> &mut Option<Motion> {
+        &mut self.motion
+    }
+    fn local_sample(&self, u: f64, v: f64) -> Option<(Point3, Vector3, f64)> {
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let n = Vector3::new(r * phi.cos(), r * phi.sin(), z);
+        Some((Point3::ORIGIN + n, n, 4.0 * PI))
+    }
+    fn local_uv(&self, point: Point3) -> (f64, f64) {
+        let theta = point.x.atan2(point.z);
+        let radius = (point - Point3::ORIGIN).magnitude();
+        let phi = (point.y / radius).acos();
+        let raw_u = theta / (2.0 * PI);
+        (1.0 - (raw_u + 0.5), 1.0 - phi / PI)
     }
 }
 
@@ -70,74 +93,73 @@ impl Sphere {
 mod sphere_tests {
     use crate::features::transformations::translation;
 
-    use super::Sphere;
+    use super::{Primitive, Sphere};
 
     #[test]
     fn test_set_tranform() {
         let mut s = Sphere::new();
         let t = translation(2.0, 3.0, 4.0);
-        s.set_transform(t.clone());
-        assert_eq!(s.transform, t);
+        s.set_transform(t).unwrap();
+        assert_eq!(s.transform(), &t);
     }
 }
 
 #[cfg(test)]
 mod normals_tests {
-    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
 
     use crate::features::{
         transformations::{rotation_z, scaling, translation},
-        tuple::Tuple,
+        tuple::{Point3, Vector3},
     };
 
-    use super::Sphere;
+    use super::{Primitive, Sphere};
 
     #[test]
     fn test_normal_on_x_axis() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(1.0, 0.0, 0.0));
-        println!("{:?}", Tuple::point(1.0, 0.0, 0.0).normalize());
-        assert_eq!(n, Tuple::vector(1.0, 0.0, 0.0))
+        let n = s.normal_at(Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(n, Vector3::new(1.0, 0.0, 0.0))
     }
 
     #[test]
     fn test_normal_on_y_axis() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(0.0, 1.0, 0.0));
-        assert_eq!(n, Tuple::vector(0.0, 1.0, 0.0))
+        let n = s.normal_at(Point3::new(0.0, 1.0, 0.0));
+        assert_eq!(n, Vector3::new(0.0, 1.0, 0.0))
     }
 
     #[test]
     fn test_normal_on_z_axis() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(0.0, 0.0, 1.0));
-        assert_eq!(n, Tuple::vector(0.0, 0.0, 1.0))
+        let n = s.normal_at(Point3::new(0.0, 0.0, 1.0));
+        assert_eq!(n, Vector3::new(0.0, 0.0, 1.0))
     }
 
     #[test]
     fn test_normal_on_nonaxial() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(
-            3.0_f32.sqrt() / 3.0,
-            3.0_f32.sqrt() / 3.0,
-            3.0_f32.sqrt() / 3.0,
+        let n = s.normal_at(Point3::new(
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
         ));
         assert_eq!(
             n,
-            Tuple::vector(
-                3.0_f32.sqrt() / 3.0,
-                3.0_f32.sqrt() / 3.0,
-                3.0_f32.sqrt() / 3.0,
+            Vector3::new(
+                3.0_f64.sqrt() / 3.0,
+                3.0_f64.sqrt() / 3.0,
+                3.0_f64.sqrt() / 3.0,
             )
         )
     }
     #[test]
     fn test_normal_is_normalized() {
         let s = Sphere::new();
-        let n = s.normal_at(Tuple::point(
-            3.0_f32.sqrt() / 3.0,
-            3.0_f32.sqrt() / 3.0,
-            3.0_f32.sqrt() / 3.0,
+        let n = s.normal_at(Point3::new(
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
+            3.0_f64.sqrt() / 3.0,
         ));
         assert_eq!(n, n.normalize());
     }
@@ -145,22 +167,22 @@ mod normals_tests {
     #[test]
     fn test_normal_translated_sphere() {
         let mut s = Sphere::new();
-        s.set_transform(translation(0.0, 1.0, 0.0));
-        let n = s.normal_at(Tuple::point(0.0, 1.70711, -FRAC_1_SQRT_2));
-        assert_eq!(n, Tuple::vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        s.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        let n = s.normal_at(Point3::new(0.0, 1.70711, -FRAC_1_SQRT_2));
+        assert_eq!(n, Vector3::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
     }
 
     #[test]
     fn test_normal_transformed_sphere() {
         let mut s = Sphere::new();
         let m = scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0);
-        s.set_transform(m);
-        let n = s.normal_at(Tuple::point(
+        s.set_transform(m).unwrap();
+        let n = s.normal_at(Point3::new(
             0.0,
-            2.0_f32.sqrt() / 2.0,
-            -(2.0_f32.sqrt() / 2.0),
+            2.0_f64.sqrt() / 2.0,
+            -(2.0_f64.sqrt() / 2.0),
         ));
-        assert_eq!(n, Tuple::vector(0.0, 0.97014, -0.24254));
+        assert_eq!(n, Vector3::new(0.0, 0.97014, -0.24254));
     }
 }
 
@@ -0,0 +1,204 @@
+use super::{
+    camera::cameras::Camera,
+    canvas::Canvas,
+    error::{Error, Result},
+    transformations::{rotation_y, translation},
+    tuple::Color,
+    world::World,
+};
+
+// how the two views are packed into one image
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StereoLayout {
+    // left view on the left half, right view on the right half
+    SideBySide,
+    // left view on top, right view below
+    OverUnder,
+    // red channel from the left eye, green and blue from the right, for
+    // red/cyan glasses
+    Anaglyph,
+}
+
+// A pair of eyes built around `camera`, which stands for the point halfway
+// between them. Each eye is turned in so both views cross at the convergence
+// distance; objects there show no parallax.
+pub struct StereoRig {
+    pub camera: Camera,
+    pub interocular: f64,
+    convergence: f64,
+}
+
+impl StereoRig {
+    // fails unless the views cross at a positive, finite distance in front
+    pub fn new(camera: Camera, interocular: f64, convergence: f64) -> Result<Self> {
+        if !(convergence > 0.0 && convergence.is_finite()) {
+            return Err(Error::InvalidConvergence(convergence));
+        }
+        Ok(Self {
+            camera,
+            interocular,
+            convergence,
+        })
+    }
+
+    pub fn convergence(&self) -> f64 {
+        self.convergence
+    }
+
+    pub fn left(&self) -> Camera {
+        self.eye(self.interocular / 2.0)
+    }
+
+    pub fn right(&self) -> Camera {
+        self.eye(-self.interocular / 2.0)
+    }
+
+    // the camera looks down -z with +x to its left, so `offset` is how far
+    // the eye sits to the left of the center
+    fn eye(&self, offset: f64) -> Camera {
+        let toe_in = -(offset / self.convergence).atan();
+        let mut eye = self.camera.clone();
+        let view = rotation_y(toe_in) * translation(-offset, 0.0, 0.0);
+        eye.set_transform(view * *self.camera.transform())
+            .expect("turning and shifting a valid view keeps it invertible");
+        eye
+    }
+
+    pub fn render(&self, world: &World, layout: StereoLayout) -> Result<Canvas> {
+        composite(
+            &self.left().render(world),
+            &self.right().render(world),
+            layout,
+        )
+    }
+}
+
+// packs two views of the same size into one image; fails if their sizes
+// differ
+pub fn composite(left: &Canvas, right: &Canvas, layout: StereoLayout) -> Result<Canvas> {
+    let (width, height) = (left.width, left.height);
+    if (right.width, right.height) != (width, height) {
+        return Err(Error::MismatchedViews {
+            left: (width, height),
+            right: (right.width, right.height),
+        });
+    }
+    let mut out = match layout {
+        StereoLayout::SideBySide => Canvas::new(width * 2, height),
+        StereoLayout::OverUnder => Canvas::new(width, height * 2),
+        StereoLayout::Anaglyph => Canvas::new(width, height),
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let (l, r) = (left.canvas[y][x], right.canvas[y][x]);
+            match layout {
+                StereoLayout::SideBySide => {
+                    out.canvas[y][x] = l;
+                    out.canvas[y][x + width] = r;
+                }
+                StereoLayout::OverUnder => {
+                    out.canvas[y][x] = l;
+                    out.canvas[y + height][x] = r;
+                }
+                StereoLayout::Anaglyph => out.canvas[y][x] = Color::new(l.r, r.g, r.b),
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod stereo_tests {
+    use std::f64::consts::PI;
+
+    use crate::features::{
+        transformations::view_transformation,
+        tuple::{Point3, Vector3},
+    };
+
+    use super::*;
+
+    fn rig() -> StereoRig {
+        let mut camera = Camera::new(21.0, 21.0, PI / 3.0).unwrap();
+        camera
+            .set_transform(view_transformation(
+                Point3::new(0.0, 0.0, -5.0),
+                Point3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        StereoRig::new(camera, 0.5, 5.0).unwrap()
+    }
+
+    #[test]
+    fn convergence_must_be_in_front_of_the_rig() {
+        let camera = Camera::new(21.0, 21.0, PI / 3.0).unwrap();
+        for convergence in [0.0, -5.0, f64::INFINITY] {
+            assert!(matches!(
+                StereoRig::new(camera.clone(), 0.5, convergence),
+                Err(Error::InvalidConvergence(_))
+            ));
+        }
+        assert_eq!(rig().convergence(), 5.0);
+    }
+
+    #[test]
+    fn eyes_are_apart_and_converge() {
+        let rig = rig();
+        let left = rig.left().ray_for_pixel(10.0, 10.0);
+        let right = rig.right().ray_for_pixel(10.0, 10.0);
+        // looking along +z from -5, the camera's left is world -x
+        assert_eq!(left.origin, Point3::new(-0.25, 0.0, -5.0));
+        assert_eq!(right.origin, Point3::new(0.25, 0.0, -5.0));
+        let t = 5.0 / left.direction.z;
+        assert_eq!(left.position(t), Point3::new(0.0, 0.0, 0.0));
+        let t = 5.0 / right.direction.z;
+        assert_eq!(right.position(t), Point3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn composites_pack_both_views() {
+        let mut left = Canvas::new(2, 1);
+        let mut right = Canvas::new(2, 1);
+        left.write_pixel(0, 0, Color::new(1.0, 0.5, 0.25)).unwrap();
+        right.write_pixel(0, 0, Color::new(0.1, 0.2, 0.3)).unwrap();
+
+        let sbs = composite(&left, &right, StereoLayout::SideBySide).unwrap();
+        assert_eq!((sbs.width, sbs.height), (4, 1));
+        assert_eq!(sbs.pixel_at(2, 0).unwrap(), Color::new(0.1, 0.2, 0.3));
+
+        let ou = composite(&left, &right, StereoLayout::OverUnder).unwrap();
+        assert_eq!((ou.width, ou.height), (2, 2));
+        assert_eq!(ou.pixel_at(0, 0).unwrap(), Color::new(1.0, 0.5, 0.25));
+        assert_eq!(ou.pixel_at(0, 1).unwrap(), Color::new(0.1, 0.2, 0.3));
+
+        let anaglyph = composite(&left, &right, StereoLayout::Anaglyph).unwrap();
+        assert_eq!(anaglyph.pixel_at(0, 0).unwrap(), Color::new(1.0, 0.2, 0.3));
+    }
+
+    #[test]
+    fn composites_need_views_of_one_size() {
+        let small = Canvas::new(2, 1);
+        let large = Canvas::new(3, 1);
+        for (left, right) in [(&small, &large), (&large, &small)] {
+            assert!(matches!(
+                composite(left, right, StereoLayout::SideBySide),
+                Err(Error::MismatchedViews { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn rendered_views_differ_by_parallax() {
+        let rig = rig();
+        let world = World::default();
+        let image = rig.render(&world, StereoLayout::SideBySide).unwrap();
+        assert_eq!((image.width, image.height), (42, 21));
+        let left = rig.left().render(&world);
+        assert_eq!(
+            image.pixel_at(3, 10).unwrap(),
+            left.pixel_at(3, 10).unwrap()
+        );
+        assert_ne!(left, rig.right().render(&world));
+    }
+}
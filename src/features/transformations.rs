@@ -1,49 +1,56 @@
-use super::{matrice::Matrice, tuple::Tuple};
+use super::{
+    error::{Error, Result},
+    matrice::Matrice4,
+    tuple::{Point3, Vector3},
+};
 
-pub fn translation(x: f32, y: f32, z: f32) -> Matrice {
-    let mut out = Matrice::identity_matrix(4);
+pub fn translation(x: f64, y: f64, z: f64) -> Matrice4 {
+    let mut out = Matrice4::identity();
     out.write_element(0, 3, x);
     out.write_element(1, 3, y);
     out.write_element(2, 3, z);
     out
 }
-pub fn scaling(x: f32, y: f32, z: f32) -> Matrice {
-    let mut out = Matrice::identity_matrix(4);
+pub fn scaling(x: f64, y: f64, z: f64) -> Matrice4 {
+    let mut out = Matrice4::identity();
     out.write_element(0, 0, x);
     out.write_element(1, 1, y);
     out.write_element(2, 2, z);
     out
 }
 
-pub fn rotation_x(rad: f32) -> Matrice {
-    let mut matrice = Matrice::identity_matrix(4);
-    matrice.write_element(1, 1, rad.cos());
-    matrice.write_element(1, 2, -rad.sin());
-    matrice.write_element(2, 1, rad.sin());
-    matrice.write_element(2, 2, rad.cos());
+pub fn rotation_x(rad: f64) -> Matrice4 {
+    let (sin, cos) = rad.sin_cos();
+    let mut matrice = Matrice4::identity();
+    matrice.write_element(1, 1, cos);
+    matrice.write_element(1, 2, -sin);
+    matrice.write_element(2, 1, sin);
+    matrice.write_element(2, 2, cos);
     matrice
 }
 
-pub fn rotation_y(rad: f32) -> Matrice {
-    let mut matrice = Matrice::identity_matrix(4);
-    matrice.write_element(0, 0, rad.cos());
-    matrice.write_element(0, 2, rad.sin());
-    matrice.write_element(2, 0, -rad.sin());
-    matrice.write_element(2, 2, rad.cos());
+pub fn rotation_y(rad: f64) -> Matrice4 {
+    let (sin, cos) = rad.sin_cos();
+    let mut matrice = Matrice4::identity();
+    matrice.write_element(0, 0, cos);
+    matrice.write_element(0, 2, sin);
+    matrice.write_element(2, 0, -sin);
+    matrice.write_element(2, 2, cos);
     matrice
 }
 
-pub fn rotation_z(rad: f32) -> Matrice {
-    let mut matrice = Matrice::identity_matrix(4);
-    matrice.write_element(0, 0, rad.cos());
-    matrice.write_element(0, 1, -rad.sin());
-    matrice.write_element(1, 0, rad.sin());
-    matrice.write_element(1, 1, rad.cos());
+pub fn rotation_z(rad: f64) -> Matrice4 {
+    let (sin, cos) = rad.sin_cos();
+    let mut matrice = Matrice4::identity();
+    matrice.write_element(0, 0, cos);
+    matrice.write_element(0, 1, -sin);
+    matrice.write_element(1, 0, sin);
+    matrice.write_element(1, 1, cos);
     matrice
 }
 
-pub fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Matrice {
-    let mut matrice = Matrice::identity_matrix(4);
+pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrice4 {
+    let mut matrice = Matrice4::identity();
     matrice.write_element(0, 1, xy);
     matrice.write_element(0, 2, xz);
     matrice.write_element(1, 0, yx);
@@ -53,44 +60,164 @@ pub fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Matrice
     matrice
 }
 
-pub fn view_transformation(from: Tuple, to: Tuple, up: Tuple) -> Matrice {
+pub fn view_transformation(from: Point3, to: Point3, up: Vector3) -> Matrice4 {
     let forward = (to - from).normalize();
     let left = forward.cross(&up.normalize());
     let true_up = left.cross(&forward);
-    Matrice {
-        size: 4,
-        matrice: vec![
-            vec![left.x, left.y, left.z, 0.0],
-            vec![true_up.x, true_up.y, true_up.z, 0.0],
-            vec![-forward.x, -forward.y, -forward.z, 0.0],
-            vec![0.0, 0.0, 0.0, 1.0],
-        ],
-    } * translation(-from.x, -from.y, -from.z)
+    let row = |v: Vector3, sign: f64| [(v.x * sign), (v.y * sign), (v.z * sign), 0.0];
+    Matrice4::new([
+        row(left, 1.0),
+        row(true_up, 1.0),
+        row(forward, -1.0),
+        [0.0, 0.0, 0.0, 1.0],
+    ]) * translation(-from.x, -from.y, -from.z)
+}
+
+// Chainable composition, applied in reading order:
+// `Matrice4::identity().rotate_x(a).scale(2.0, 2.0, 2.0).translate(1.0, 0.0, 0.0)`
+// rotates first and translates last.
+impl Matrice4 {
+    pub fn then(self, next: Matrice4) -> Matrice4 {
+        next * self
+    }
+    pub fn translate(self, x: f64, y: f64, z: f64) -> Matrice4 {
+        self.then(translation(x, y, z))
+    }
+    pub fn scale(self, x: f64, y: f64, z: f64) -> Matrice4 {
+        self.then(scaling(x, y, z))
+    }
+    pub fn rotate_x(self, rad: f64) -> Matrice4 {
+        self.then(rotation_x(rad))
+    }
+    pub fn rotate_y(self, rad: f64) -> Matrice4 {
+        self.then(rotation_y(rad))
+    }
+    pub fn rotate_z(self, rad: f64) -> Matrice4 {
+        self.then(rotation_z(rad))
+    }
+    pub fn shear(self, xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Matrice4 {
+        self.then(shearing(xy, xz, yx, yz, zx, zy))
+    }
+}
+
+// A transformation matrix kept together with its inverse and inverse
+// transpose, which are worked out once here instead of for every ray.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Transform {
+    matrix: Matrice4,
+    inverse: Matrice4,
+    inverse_transpose: Matrice4,
+}
+
+impl Transform {
+    // fails for a singular matrix, which no ray could be inverted through,
+    // and for one with NaN or infinite entries
+    pub fn new(matrix: Matrice4) -> Result<Self> {
+        if !matrix.is_finite() {
+            return Err(Error::NonFiniteTransform(Box::new(matrix)));
+        }
+        let inverse = matrix
+            .inverse()
+            .ok_or_else(|| Error::SingularTransform(Box::new(matrix)))?;
+        let inverse_transpose = inverse.transpose();
+        Ok(Self {
+            matrix,
+            inverse,
+            inverse_transpose,
+        })
+    }
+    pub fn matrix(&self) -> &Matrice4 {
+        &self.matrix
+    }
+    pub fn inverse(&self) -> &Matrice4 {
+        &self.inverse
+    }
+    pub fn inverse_transpose(&self) -> &Matrice4 {
+        &self.inverse_transpose
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            matrix: Matrice4::IDENTITY,
+            inverse: Matrice4::IDENTITY,
+            inverse_transpose: Matrice4::IDENTITY,
+        }
+    }
+}
+
+impl TryFrom<Matrice4> for Transform {
+    type Error = Error;
+
+    fn try_from(matrix: Matrice4) -> Result<Self> {
+        Self::new(matrix)
+    }
+}
+
+#[cfg(test)]
+mod transform_cache_tests {
+    use super::*;
+
+    #[test]
+    fn transform_caches_inverse_and_inverse_transpose() {
+        let m = translation(1.0, 2.0, 3.0) * scaling(2.0, 4.0, 8.0);
+        let t = Transform::new(m).unwrap();
+        assert_eq!(t.matrix(), &m);
+        assert_eq!(t.inverse(), &m.inverse().unwrap());
+        assert_eq!(t.inverse_transpose(), &m.inverse().unwrap().transpose());
+        assert_eq!(Transform::default().inverse(), &Matrice4::identity());
+    }
+
+    #[test]
+    fn singular_transforms_are_rejected() {
+        let flat = scaling(0.0, 1.0, 1.0);
+        assert!(matches!(
+            Transform::new(flat),
+            Err(Error::SingularTransform(m)) if *m == flat
+        ));
+        assert!(Transform::try_from(translation(1.0, 0.0, 0.0)).is_ok());
+    }
+
+    #[test]
+    fn non_finite_transforms_are_rejected() {
+        for bad in [f64::NAN, f64::INFINITY] {
+            let m = scaling(bad, 1.0, 1.0);
+            assert!(m.inverse().is_none());
+            assert!(matches!(
+                Transform::new(m),
+                Err(Error::NonFiniteTransform(_))
+            ));
+        }
+    }
 }
 
 #[cfg(test)]
 mod translation_tests {
-    use crate::features::{transformations::translation, tuple::Tuple};
+    use crate::features::{
+        transformations::translation,
+        tuple::{Point3, Vector3},
+    };
 
     #[test]
     fn test_multiplying_translation_matrix_to_point() {
         let transform = translation(5.0, -3.0, 2.0);
-        let p = Tuple::point(-3.0, 4.0, 5.0);
-        assert_eq!(transform * p, Tuple::point(2.0, 1.0, 7.0))
+        let p = Point3::new(-3.0, 4.0, 5.0);
+        assert_eq!(transform * p, Point3::new(2.0, 1.0, 7.0))
     }
 
     #[test]
     fn test_multiplying_inverse_translation_matrix_to_point() {
         let transform = translation(5.0, -3.0, 2.0);
         let inv = transform.inverse().unwrap();
-        let p = Tuple::point(-3.0, 4.0, 5.0);
-        assert_eq!(inv * p, Tuple::point(-8.0, 7.0, 3.0));
+        let p = Point3::new(-3.0, 4.0, 5.0);
+        assert_eq!(inv * p, Point3::new(-8.0, 7.0, 3.0));
     }
 
     #[test]
     fn test_multiplying_translation_matrix_to_vector() {
         let transform = translation(5.0, -3.0, 2.0);
-        let v = Tuple::vector(-3.0, 4.0, 5.0);
+        let v = Vector3::new(-3.0, 4.0, 5.0);
         assert_eq!(transform * v, v);
     }
 }
@@ -101,83 +228,83 @@ mod scaling_tests {
     #[test]
     fn test_scaling_matrix_to_point() {
         let transform = scaling(2.0, 3.0, 4.0);
-        let p = Tuple::point(-4.0, 6.0, 8.0);
-        assert_eq!(transform * p, Tuple::point(-8.0, 18.0, 32.0))
+        let p = Point3::new(-4.0, 6.0, 8.0);
+        assert_eq!(transform * p, Point3::new(-8.0, 18.0, 32.0))
     }
 
     #[test]
     fn test_scaling_matrix_to_vector() {
         let transform = scaling(2.0, 3.0, 4.0);
-        let p = Tuple::vector(-4.0, 6.0, 8.0);
-        assert_eq!(transform * p, Tuple::vector(-8.0, 18.0, 32.0))
+        let p = Vector3::new(-4.0, 6.0, 8.0);
+        assert_eq!(transform * p, Vector3::new(-8.0, 18.0, 32.0))
     }
 
     #[test]
     fn test_inverse_of_scaling_matrix() {
         let transform = scaling(2.0, 3.0, 4.0);
         let inv = transform.inverse().unwrap();
-        let p = Tuple::point(-4.0, 6.0, 8.0);
-        assert_eq!(inv * p, Tuple::point(-2.0, 2.0, 2.0))
+        let p = Point3::new(-4.0, 6.0, 8.0);
+        assert_eq!(inv * p, Point3::new(-2.0, 2.0, 2.0))
     }
 
     #[test]
     fn test_reflection_scaling_by_negative_value() {
         let transform = scaling(-1.0, 1.0, 1.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
-        assert_eq!(transform * p, Tuple::point(-2.0, 3.0, 4.0))
+        let p = Point3::new(2.0, 3.0, 4.0);
+        assert_eq!(transform * p, Point3::new(-2.0, 3.0, 4.0))
     }
 }
 
 #[cfg(test)]
 mod rotation_tests {
-    use std::f32::consts::PI;
+    use std::f64::consts::PI;
 
     use super::*;
 
     #[test]
     fn test_rotation_x() {
-        let p = Tuple::point(0.0, 1.0, 0.0);
+        let p = Point3::new(0.0, 1.0, 0.0);
         let half_quarter = rotation_x(PI / 4.0);
         let full_quarter = rotation_x(PI / 2.0);
         assert_eq!(
             half_quarter * p,
-            Tuple::point(0.0, 2.0_f32.sqrt() / 2.0, 2.0_f32.sqrt() / 2.0)
+            Point3::new(0.0, 2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0)
         );
-        assert_eq!(full_quarter * p, Tuple::point(0.0, 0.0, 1.0));
+        assert_eq!(full_quarter * p, Point3::new(0.0, 0.0, 1.0));
     }
 
     #[test]
     fn test_inverse_rotation_x() {
-        let p = Tuple::point(0.0, 1.0, 0.0);
+        let p = Point3::new(0.0, 1.0, 0.0);
         let half_quarter = rotation_x(PI / 4.0);
         let inv = half_quarter.inverse().unwrap();
         assert_eq!(
             inv * p,
-            Tuple::point(0.0, 2.0_f32.sqrt() / 2.0, -(2.0_f32.sqrt() / 2.0))
+            Point3::new(0.0, 2.0_f64.sqrt() / 2.0, -(2.0_f64.sqrt() / 2.0))
         );
     }
     #[test]
     fn test_rotation_y() {
-        let p = Tuple::point(0.0, 0.0, 1.0);
+        let p = Point3::new(0.0, 0.0, 1.0);
         let half_quarter = rotation_y(PI / 4.0);
         let full_quarter = rotation_y(PI / 2.0);
         assert_eq!(
             half_quarter * p,
-            Tuple::point(2.0_f32.sqrt() / 2.0, 0.0, 2.0_f32.sqrt() / 2.0)
+            Point3::new(2.0_f64.sqrt() / 2.0, 0.0, 2.0_f64.sqrt() / 2.0)
         );
-        assert_eq!(full_quarter * p, Tuple::point(1.0, 0.0, 0.0));
+        assert_eq!(full_quarter * p, Point3::new(1.0, 0.0, 0.0));
     }
 
     #[test]
     fn test_rotation_z() {
-        let p = Tuple::point(0.0, 1.0, 0.0);
+        let p = Point3::new(0.0, 1.0, 0.0);
         let half_quarter = rotation_z(PI / 4.0);
         let full_quarter = rotation_z(PI / 2.0);
         assert_eq!(
             half_quarter * p,
-            Tuple::point(-(2.0_f32.sqrt() / 2.0), 2.0_f32.sqrt() / 2.0, 0.0)
+            Point3::new(-(2.0_f64.sqrt() / 2.0), 2.0_f64.sqrt() / 2.0, 0.0)
         );
-        assert_eq!(full_quarter * p, Tuple::point(-1.0, 0.0, 0.0));
+        assert_eq!(full_quarter * p, Point3::new(-1.0, 0.0, 0.0));
     }
 }
 
@@ -188,30 +315,44 @@ mod shearing_tests {
     #[test]
     fn test_shearing_transformation_x_to_y() {
         let transform = shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
-        assert_eq!(transform * p, Tuple::point(5.0, 3.0, 4.0));
+        let p = Point3::new(2.0, 3.0, 4.0);
+        assert_eq!(transform * p, Point3::new(5.0, 3.0, 4.0));
     }
     #[test]
     fn test_shearing_transformation_y_to_z() {
         let transform = shearing(0.0, 0.0, 1.0, 0.0, 0.0, 0.0);
-        let p = Tuple::point(2.0, 3.0, 4.0);
-        assert_eq!(transform * p, Tuple::point(2.0, 5.0, 4.0));
+        let p = Point3::new(2.0, 3.0, 4.0);
+        assert_eq!(transform * p, Point3::new(2.0, 5.0, 4.0));
     }
 }
 
 #[cfg(test)]
 mod chained_tests {
     use super::*;
-    use std::f32::consts::PI;
+    use std::f64::consts::PI;
 
     #[test]
     fn test_chained_tranformation() {
-        let p = Tuple::point(1.0, 0.0, 1.0);
+        let p = Point3::new(1.0, 0.0, 1.0);
         let a = rotation_x(PI / 2.0);
         let b = scaling(5.0, 5.0, 5.0);
         let c = translation(10.0, 5.0, 7.0);
         let t = c * b * a;
-        assert_eq!(t * p, Tuple::point(15.0, 0.0, 7.0));
+        assert_eq!(t * p, Point3::new(15.0, 0.0, 7.0));
+    }
+
+    #[test]
+    fn test_fluent_tranformation() {
+        let p = Point3::new(1.0, 0.0, 1.0);
+        let t = Matrice4::identity()
+            .rotate_x(PI / 2.0)
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0);
+        assert_eq!(
+            t,
+            translation(10.0, 5.0, 7.0) * scaling(5.0, 5.0, 5.0) * rotation_x(PI / 2.0)
+        );
+        assert_eq!(t * p, Point3::new(15.0, 0.0, 7.0));
     }
 }
 
@@ -221,36 +362,36 @@ mod view_transformation_tests {
 
     #[test]
     fn test_default_view() {
-        let from = Tuple::point(0.0, 0.0, 0.0);
-        let to = Tuple::point(0.0, 0.0, -1.0);
-        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let from = Point3::new(0.0, 0.0, 0.0);
+        let to = Point3::new(0.0, 0.0, -1.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
         let t = view_transformation(from, to, up);
-        assert_eq!(t, Matrice::identity_matrix(4))
+        assert_eq!(t, Matrice4::identity())
     }
 
     #[test]
     fn test_positive_z_direction() {
-        let from = Tuple::point(0.0, 0.0, 0.0);
-        let to = Tuple::point(0.0, 0.0, 1.0);
-        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let from = Point3::new(0.0, 0.0, 0.0);
+        let to = Point3::new(0.0, 0.0, 1.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
         let t = view_transformation(from, to, up);
         assert_eq!(t, scaling(-1.0, 1.0, -1.0))
     }
 
     #[test]
     fn test_moving_world() {
-        let from = Tuple::point(0.0, 0.0, 8.0);
-        let to = Tuple::point(0.0, 0.0, 0.0);
-        let up = Tuple::vector(0.0, 1.0, 0.0);
+        let from = Point3::new(0.0, 0.0, 8.0);
+        let to = Point3::new(0.0, 0.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
         let t = view_transformation(from, to, up);
         assert_eq!(t, translation(0.0, 0.0, -8.0))
     }
 
     #[test]
     fn test_arbitrary_view() {
-        let from = Tuple::point(1.0, 3.0, 2.0);
-        let to = Tuple::point(4.0, -2.0, 8.0);
-        let up = Tuple::vector(1.0, 1.0, 0.0);
+        let from = Point3::new(1.0, 3.0, 2.0);
+        let to = Point3::new(4.0, -2.0, 8.0);
+        let up = Vector3::new(1.0, 1.0, 0.0);
         let t = view_transformation(from, to, up);
         println!("{:?}", t);
     }
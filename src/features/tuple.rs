@@ -1,237 +1,428 @@
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
+use super::{
+    error::{Error, Result},
+    simd::{F64x4, Simd},
+};
+
+// tolerance for comparing tuples and the threshold for treating a direction
+// component as zero
+pub const EPSILON: f64 = 0.00001;
+
+fn approx(a: f64, b: f64) -> bool {
+    (a - b).abs() < EPSILON
+}
+
+// -0.0 prints and compares oddly in tests, so negation leaves zeros alone
+fn negate(v: f64) -> f64 {
+    if v == 0.0 {
+        0.0
+    } else {
+        -v
+    }
+}
+
+// A location in space. Points can be moved by vectors and subtracted into
+// vectors, but not added, scaled or dotted.
 #[derive(Default, Clone, Copy, Debug)]
-pub struct Tuple {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
-    pub w: TupleType,
+pub struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
 }
-const EPSILON: f32 = 0.00001;
 
-impl Tuple {
-    pub fn new(x: f32, y: f32, z: f32, w: TupleType) -> Self {
-        Tuple { x, y, z, w }
+impl Point3 {
+    pub const ORIGIN: Point3 = Point3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Point3 { x, y, z }
     }
-    pub fn point(x: f32, y: f32, z: f32) -> Self {
-        Tuple {
-            x,
-            y,
-            z,
-            w: TupleType::Point,
-        }
+    // x, y and z in the first three lanes of a SIMD register, the last is zero
+    pub fn lanes(&self) -> F64x4 {
+        Simd([self.x, self.y, self.z, 0.0])
     }
-    pub fn vector(x: f32, y: f32, z: f32) -> Self {
-        Tuple {
-            x,
-            y,
-            z,
-            w: TupleType::Vector,
-        }
+    pub fn from_lanes(lanes: F64x4) -> Self {
+        Self::new(lanes[0], lanes[1], lanes[2])
     }
+}
 
-    pub fn color(x: f32, y: f32, z: f32) -> Self {
-        Tuple {
-            x,
-            y,
-            z,
-            w: TupleType::Color,
-        }
+// A direction and length. Transforming a vector ignores translation.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Vector3 { x, y, z }
     }
-    pub fn as_str(&self) -> String {
-        format!("{} {} {}\n", self.x as i32, self.y as i32, self.z as i32)
+    pub fn lanes(&self) -> F64x4 {
+        Simd([self.x, self.y, self.z, 0.0])
     }
-    pub fn clamp(&self) -> Self {
-        let mut x = self.x * 255.0;
-        let mut y = self.y * 255.0;
-        let mut z = self.z * 255.0;
-        //TODO:refactor
-        if x > 255.0 {
-            x = 255.0;
-        }
-        if x < 0.0 {
-            x = 0.0;
-        }
-        if y > 255.0 {
-            y = 255.0;
-        }
-        if y < 0.0 {
-            y = 0.0;
-        }
-        if z > 255.0 {
-            z = 255.0;
-        }
-        if z < 0.0 {
-            z = 0.0;
-        }
-        Self::color(x, y, z)
+    pub fn from_lanes(lanes: F64x4) -> Self {
+        Self::new(lanes[0], lanes[1], lanes[2])
     }
-
-    pub fn default_color() -> Self {
-        Tuple {
-            x: 0.0,
-            y: 0.0,
-            z: 0.0,
-            w: TupleType::Color,
-        }
-    }
-    pub fn magnitude(&self) -> f32 {
-        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    pub fn magnitude(&self) -> f64 {
+        self.dot(self).sqrt()
     }
     pub fn normalize(&self) -> Self {
         let mag = self.magnitude();
-        Self::vector(self.x / mag, self.y / mag, self.z / mag)
+        Self::from_lanes(self.lanes() / Simd::splat(mag))
     }
 
-    pub fn dot(&self, other: &Tuple) -> f32 {
-        self.x * other.x + self.y * other.y + self.z * other.z
+    pub fn dot(&self, other: &Vector3) -> f64 {
+        (self.lanes() * other.lanes()).sum()
     }
 
-    pub fn cross(&self, other: &Tuple) -> Self {
-        Self::vector(
-            self.y * other.z - self.z * other.y,
-            self.z * other.x - self.x * other.z,
-            self.x * other.y - self.y * other.x,
-        )
+    pub fn cross(&self, other: &Vector3) -> Self {
+        let yzx = |t: &Vector3| Simd([t.y, t.z, t.x, 0.0]);
+        let zxy = |t: &Vector3| Simd([t.z, t.x, t.y, 0.0]);
+        Self::from_lanes(yzx(self) * zxy(other) - zxy(self) * yzx(other))
     }
     pub fn reflect(&self, normal: &Self) -> Self {
         *self - *normal * 2.0 * self.dot(normal)
     }
 }
 
-#[cfg(test)]
-mod tuple_tests {
-    use super::Tuple;
+// Linear RGB radiance. Colors add, scale and multiply component-wise.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
 
-    #[test]
-    fn test_cross() {
-        let tuple = Tuple::vector(1.0, 2.0, 3.0);
-        let other = Tuple::vector(2.0, 3.0, 4.0);
-        let mut res = tuple.cross(&other);
-        assert!(res == Tuple::vector(-1.0, 2.0, -1.0));
-        res = other.cross(&tuple);
-        assert!(res == Tuple::vector(1.0, -2.0, 1.0));
+impl Color {
+    pub fn new(r: f64, g: f64, b: f64) -> Self {
+        Color { r, g, b }
     }
-    #[test]
-    fn test_mul_colors() {
-        let color_tuple = Tuple::color(1.0, 0.2, 0.4);
-        let color_other_tuple = Tuple::color(0.9, 1.0, 0.1);
-        let res = color_other_tuple * color_tuple;
-        assert!(res == Tuple::color(0.9, 0.2, 0.04))
+    pub fn black() -> Self {
+        Self::default()
+    }
+    pub fn lanes(&self) -> F64x4 {
+        Simd([self.r, self.g, self.b, 0.0])
+    }
+    pub fn from_lanes(lanes: F64x4) -> Self {
+        Self::new(lanes[0], lanes[1], lanes[2])
+    }
+    pub fn as_str(&self) -> String {
+        format!("{} {} {}\n", self.r as i32, self.g as i32, self.b as i32)
+    }
+    pub fn clamp(&self) -> Self {
+        let r = (self.r * 255.0).clamp(0.0, 255.0);
+        let g = (self.g * 255.0).clamp(0.0, 255.0);
+        let b = (self.b * 255.0).clamp(0.0, 255.0);
+        Self::new(r, g, b)
+    }
+    // perceived brightness, with the Rec. 709 weights
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
     }
 }
 
-#[cfg(test)]
-mod reflect_tests {
-    use super::Tuple;
-
-    #[test]
-    fn test_reflecting_at_45() {
-        let v = Tuple::vector(1.0, -1.0, 0.0);
-        let n = Tuple::vector(0.0, 1.0, 0.0);
-        let r = v.reflect(&n);
-        assert_eq!(r, Tuple::vector(1.0, 1.0, 0.0));
+impl PartialEq for Point3 {
+    fn eq(&self, other: &Self) -> bool {
+        approx(self.x, other.x) && approx(self.y, other.y) && approx(self.z, other.z)
     }
+}
+impl Eq for Point3 {}
 
-    #[test]
-    fn test_reflecting_off_slanted_surface() {
-        let v = Tuple::vector(0.0, -1.0, 0.0);
-        let n = Tuple::vector(2.0_f32.sqrt() / 2.0, 2.0_f32.sqrt() / 2.0, 0.0);
-        let r = v.reflect(&n);
-        assert_eq!(r, Tuple::vector(1.0, 0.0, 0.0));
+impl PartialEq for Vector3 {
+    fn eq(&self, other: &Self) -> bool {
+        approx(self.x, other.x) && approx(self.y, other.y) && approx(self.z, other.z)
     }
 }
+impl Eq for Vector3 {}
 
-impl PartialEq for Tuple {
+impl PartialEq for Color {
     fn eq(&self, other: &Self) -> bool {
-        (self.x - other.x).abs() < EPSILON
-            && (self.y - other.y).abs() < EPSILON
-            && (self.z - other.z).abs() < EPSILON
-            && self.w == other.w
+        approx(self.r, other.r) && approx(self.g, other.g) && approx(self.b, other.b)
     }
 }
-impl Eq for Tuple {}
+impl Eq for Color {}
 
-impl Add for Tuple {
+impl Add<Vector3> for Point3 {
+    type Output = Point3;
+    fn add(self, rhs: Vector3) -> Point3 {
+        Point3::from_lanes(self.lanes() + rhs.lanes())
+    }
+}
+
+impl Sub<Vector3> for Point3 {
+    type Output = Point3;
+    fn sub(self, rhs: Vector3) -> Point3 {
+        Point3::from_lanes(self.lanes() - rhs.lanes())
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Vector3;
+    fn sub(self, rhs: Point3) -> Vector3 {
+        Vector3::from_lanes(self.lanes() - rhs.lanes())
+    }
+}
+
+impl Add for Vector3 {
     type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        Tuple::new(
-            self.x + rhs.x,
-            self.y + rhs.y,
-            self.z + rhs.z,
-            self.w + rhs.w,
-        )
+    fn add(self, rhs: Self) -> Self {
+        Self::from_lanes(self.lanes() + rhs.lanes())
     }
 }
 
-impl Sub for Tuple {
+impl Sub for Vector3 {
     type Output = Self;
-    fn sub(self, rhs: Self) -> Self::Output {
-        Tuple::new(
-            self.x - rhs.x,
-            self.y - rhs.y,
-            self.z - rhs.z,
-            self.w + rhs.w,
-        )
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_lanes(self.lanes() - rhs.lanes())
     }
 }
 
-impl Neg for Tuple {
+impl Neg for Vector3 {
     type Output = Self;
-    fn neg(self) -> Self::Output {
-        let mut x = -self.x;
-        if self.x == 0.0 {
-            x = 0.0;
-        }
-        let mut y = -self.y;
-        if self.y == 0.0 {
-            y = 0.0;
-        }
-        let mut z = -self.z;
-        if self.z == 0.0 {
-            z = 0.0;
-        }
-        Tuple::new(x, y, z, self.w)
+    fn neg(self) -> Self {
+        Self::new(negate(self.x), negate(self.y), negate(self.z))
+    }
+}
+
+impl Mul<f64> for Vector3 {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        Self::from_lanes(self.lanes() * rhs)
+    }
+}
+
+impl Div<f64> for Vector3 {
+    type Output = Self;
+    fn div(self, rhs: f64) -> Self {
+        Self::from_lanes(self.lanes() / Simd::splat(rhs))
+    }
+}
+
+impl Add for Color {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::from_lanes(self.lanes() + rhs.lanes())
+    }
+}
+
+impl Sub for Color {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::from_lanes(self.lanes() - rhs.lanes())
     }
 }
 
-impl Div<f32> for Tuple {
+impl Mul for Color {
     type Output = Self;
-    fn div(self, rhs: f32) -> Self::Output {
-        Self::new(self.x / rhs, self.y / rhs, self.z / rhs, self.w)
+    fn mul(self, rhs: Self) -> Self {
+        Self::from_lanes(self.lanes() * rhs.lanes())
     }
 }
 
-impl Mul<f32> for Tuple {
+impl Mul<f64> for Color {
     type Output = Self;
-    fn mul(self, rhs: f32) -> Self::Output {
-        Self::new(self.x * rhs, self.y * rhs, self.z * rhs, self.w)
+    fn mul(self, rhs: f64) -> Self {
+        Self::from_lanes(self.lanes() * rhs)
     }
 }
-impl Mul for Tuple {
+
+impl Div<f64> for Color {
     type Output = Self;
-    fn mul(self, rhs: Self) -> Self::Output {
-        Self::color(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z)
+    fn div(self, rhs: f64) -> Self {
+        Self::from_lanes(self.lanes() / Simd::splat(rhs))
     }
 }
 
+// The homogeneous tuple of the book, tagged at runtime with what it holds.
+// Rendering code works on `Point3`, `Vector3` and `Color`; a tuple is only a
+// way to build one of those, or to feed the general `Matrice`.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Tuple {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: TupleType,
+}
+
 #[derive(Default, Copy, Clone, Debug, PartialEq)]
 pub enum TupleType {
     Vector,
     #[default]
     Point,
     Color,
-    Undefined,
 }
 
-impl Add for TupleType {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Self::Point, Self::Vector) | (Self::Vector, Self::Point) => Self::Point,
-            (Self::Vector, Self::Vector) | (Self::Point, Self::Point) => Self::Vector,
-            (Self::Color, Self::Color) => Self::Color,
-            _ => Self::Undefined,
+impl Tuple {
+    pub fn new(x: f64, y: f64, z: f64, w: TupleType) -> Self {
+        Tuple { x, y, z, w }
+    }
+    pub fn point(x: f64, y: f64, z: f64) -> Self {
+        Self::new(x, y, z, TupleType::Point)
+    }
+    pub fn vector(x: f64, y: f64, z: f64) -> Self {
+        Self::new(x, y, z, TupleType::Vector)
+    }
+    pub fn color(x: f64, y: f64, z: f64) -> Self {
+        Self::new(x, y, z, TupleType::Color)
+    }
+}
+
+impl PartialEq for Tuple {
+    fn eq(&self, other: &Self) -> bool {
+        approx(self.x, other.x)
+            && approx(self.y, other.y)
+            && approx(self.z, other.z)
+            && self.w == other.w
+    }
+}
+impl Eq for Tuple {}
+
+impl Tuple {
+    // the x, y and z of a tuple that holds a `kind`
+    fn expect(self, kind: TupleType) -> Result<(f64, f64, f64)> {
+        if self.w != kind {
+            return Err(Error::MismatchedTuple {
+                expected: kind,
+                found: self.w,
+            });
         }
+        Ok((self.x, self.y, self.z))
+    }
+}
+
+impl TryFrom<Tuple> for Point3 {
+    type Error = Error;
+
+    fn try_from(t: Tuple) -> Result<Self> {
+        let (x, y, z) = t.expect(TupleType::Point)?;
+        Ok(Point3::new(x, y, z))
+    }
+}
+
+impl TryFrom<Tuple> for Vector3 {
+    type Error = Error;
+
+    fn try_from(t: Tuple) -> Result<Self> {
+        let (x, y, z) = t.expect(TupleType::Vector)?;
+        Ok(Vector3::new(x, y, z))
+    }
+}
+
+impl TryFrom<Tuple> for Color {
+    type Error = Error;
+
+    fn try_from(t: Tuple) -> Result<Self> {
+        let (x, y, z) = t.expect(TupleType::Color)?;
+        Ok(Color::new(x, y, z))
+    }
+}
+
+impl From<Point3> for Tuple {
+    fn from(p: Point3) -> Self {
+        Tuple::point(p.x, p.y, p.z)
+    }
+}
+
+impl From<Vector3> for Tuple {
+    fn from(v: Vector3) -> Self {
+        Tuple::vector(v.x, v.y, v.z)
+    }
+}
+
+impl From<Color> for Tuple {
+    fn from(c: Color) -> Self {
+        Tuple::color(c.r, c.g, c.b)
+    }
+}
+
+#[cfg(test)]
+mod tuple_tests {
+    use super::*;
+
+    #[test]
+    fn test_cross() {
+        let tuple = Vector3::new(1.0, 2.0, 3.0);
+        let other = Vector3::new(2.0, 3.0, 4.0);
+        let mut res = tuple.cross(&other);
+        assert!(res == Vector3::new(-1.0, 2.0, -1.0));
+        res = other.cross(&tuple);
+        assert!(res == Vector3::new(1.0, -2.0, 1.0));
+    }
+    #[test]
+    fn test_mul_colors() {
+        let color_tuple = Color::new(1.0, 0.2, 0.4);
+        let color_other_tuple = Color::new(0.9, 1.0, 0.1);
+        let res = color_other_tuple * color_tuple;
+        assert!(res == Color::new(0.9, 0.2, 0.04))
+    }
+
+    #[test]
+    fn points_and_vectors_combine_by_kind() {
+        let p = Point3::new(3.0, 2.0, 1.0);
+        let q = Point3::new(5.0, 6.0, 7.0);
+        let v = Vector3::new(1.0, 0.5, 0.0);
+        assert_eq!(p - q, Vector3::new(-2.0, -4.0, -6.0));
+        assert_eq!(p + v, Point3::new(4.0, 2.5, 1.0));
+        assert_eq!(p - v, Point3::new(2.0, 1.5, 1.0));
+        assert_eq!(-v, Vector3::new(-1.0, -0.5, 0.0));
+    }
+
+    #[test]
+    fn converting_from_tuples() {
+        assert_eq!(
+            Point3::try_from(Tuple::point(1.0, 2.0, 3.0)).unwrap(),
+            Point3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            Vector3::try_from(Tuple::vector(1.0, 2.0, 3.0)).unwrap(),
+            Vector3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            Color::try_from(Tuple::color(0.1, 0.2, 0.3)).unwrap(),
+            Color::new(0.1, 0.2, 0.3)
+        );
+        assert_eq!(
+            Tuple::from(Vector3::new(1.0, 0.0, 0.0)),
+            Tuple::vector(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn converting_tuples_of_the_wrong_kind_fails() {
+        assert!(matches!(
+            Point3::try_from(Tuple::vector(1.0, 2.0, 3.0)),
+            Err(Error::MismatchedTuple {
+                expected: TupleType::Point,
+                found: TupleType::Vector,
+            })
+        ));
+        assert!(Vector3::try_from(Tuple::point(1.0, 2.0, 3.0)).is_err());
+        assert!(Color::try_from(Tuple::vector(0.1, 0.2, 0.3)).is_err());
+    }
+}
+
+#[cfg(test)]
+mod reflect_tests {
+    use super::Vector3;
+
+    #[test]
+    fn test_reflecting_at_45() {
+        let v = Vector3::new(1.0, -1.0, 0.0);
+        let n = Vector3::new(0.0, 1.0, 0.0);
+        let r = v.reflect(&n);
+        assert_eq!(r, Vector3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_reflecting_off_slanted_surface() {
+        let v = Vector3::new(0.0, -1.0, 0.0);
+        let n = Vector3::new(2.0_f64.sqrt() / 2.0, 2.0_f64.sqrt() / 2.0, 0.0);
+        let r = v.reflect(&n);
+        assert_eq!(r, Vector3::new(1.0, 0.0, 0.0));
     }
 }
@@ -1,111 +1,383 @@
+use std::f64::consts::PI;
+
 use super::{
-    intersections::{computations::Computation, hit, intersections, Intersection},
-    lights::Light,
-    materials::{lightning, Material},
+    background::Background,
+    environment::Environment,
+    intersections::{
+        computations::{surface_offset, Computation},
+        intersections, Intersection,
+    },
+    lights::{Light, Sun},
+    materials::{direct_lightning, lightning, Material},
+    occlusion::AmbientOcclusion,
     rays::Ray,
+    sampling::{fold_above, glossy_lobe},
     shape::Shape,
+    sky::Sky,
     spheres::Sphere,
     transformations::scaling,
-    tuple::Tuple,
+    tuple::{Color, Point3, Vector3},
 };
 
+// Names a shape in a world. Shapes are only ever added to the arena, so an
+// id stays valid and keeps naming the same shape for the life of the world.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ShapeId(usize);
+
 pub struct World {
     pub light: Light,
-    pub shapes: Vec<Shape>,
+    // the arena, indexed by `ShapeId`
+    shapes: Vec<Shape>,
+    // emissive shapes are sampled on an n by n grid, n * n shadow rays each
+    pub area_light_samples: usize,
+    // seen by every ray that misses all the shapes
+    pub background: Background,
+    // image-based lighting, sampled on an n by n grid like the emitters
+    pub environment: Option<Environment>,
+    pub environment_samples: usize,
+    pub sun: Option<Sun>,
+    // darkens the ambient term in creases when set
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    // rough reflections and refractions trace n * n rays over their lobe
+    pub glossy_samples: usize,
+    // bounces of reflection and refraction followed from a camera ray
+    pub max_depth: u32,
 }
 
 impl World {
+    // the shapes get ids in the order given
     pub fn new(light: Light, shapes: &[Shape]) -> Self {
         Self {
             light,
             shapes: shapes.to_vec(),
+            area_light_samples: 4,
+            background: Background::default(),
+            environment: None,
+            environment_samples: 4,
+            sun: None,
+            ambient_occlusion: None,
+            glossy_samples: 4,
+            max_depth: 5,
         }
     }
+    pub fn add(&mut self, shape: Shape) -> ShapeId {
+        self.shapes.push(shape);
+        ShapeId(self.shapes.len() - 1)
+    }
+    pub fn shape(&self, id: ShapeId) -> &Shape {
+        &self.shapes[id.0]
+    }
+    pub fn shape_mut(&mut self, id: ShapeId) -> &mut Shape {
+        &mut self.shapes[id.0]
+    }
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+    pub fn ids(&self) -> impl Iterator<Item = ShapeId> {
+        (0..self.shapes.len()).map(ShapeId)
+    }
+    // the id of a shape borrowed from this world, e.g. by an intersection
+    pub fn id_of(&self, shape: &Shape) -> Option<ShapeId> {
+        self.shapes
+            .iter()
+            .position(|s| std::ptr::eq(s, shape))
+            .map(ShapeId)
+    }
     pub fn set_light(&mut self, light: Light) {
         self.light = light;
     }
-    pub fn shade_hit(&self, comps: &Computation) -> Tuple {
-        let shadowed = self.is_shadowed(&comps.over_point);
+    // an outdoor setting: the sky is seen in the background and lights the
+    // scene along with its sun
+    pub fn set_sky(&mut self, sky: Sky) {
+        self.sun = Some(sky.sun());
+        self.environment = Some(sky.environment(64, 32));
+        self.background = Background::Sky(sky);
+    }
+    // `remaining` is how many more reflected or refracted rays may be traced
+    // from here, usually `max_depth` for a camera ray
+    pub fn shade_hit(&self, comps: &Computation, remaining: u32) -> Color {
+        let shadowed = self.is_occluded(&comps.over_point, &self.light.position, comps.time);
+        let mut material = comps.object.material();
+        if let Some(occlusion) = &self.ambient_occlusion {
+            if material.ambient > 0.0 {
+                material.ambient *= occlusion.visibility(self, comps);
+            }
+        }
         let surface = lightning(
-            &comps.object.material(),
-            &comps.object,
+            &material,
+            &comps.surface(),
             &self.light,
             &comps.over_point,
             &comps.eyev,
             &comps.normalv,
             shadowed,
         );
-        let reflected = self.reflected_color(comps);
-        surface + reflected
+        let grid = sample_grid(self.area_light_samples);
+        let emitted = comps.object.material().emission() + self.emitter_lighting(comps, &grid);
+        let environment = self.environment_lighting(comps, &sample_grid(self.environment_samples));
+        let sun = self.sun_lighting(comps);
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+        let lit = surface + emitted + environment + sun;
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            // glass reflects more of what it sees at grazing angles
+            let reflectance = comps.schlick();
+            return lit + reflected * reflectance + refracted * (1.0 - reflectance);
+        }
+        lit + reflected + refracted
+    }
+
+    pub fn sun_lighting(&self, comps: &Computation) -> Color {
+        let Some(sun) = &self.sun else {
+            return Color::black();
+        };
+        if sun.direction.dot(&comps.normalv) <= 0.0 {
+            return Color::black();
+        }
+        let shadow = Ray::with_time(comps.over_point, sun.direction, comps.time);
+        if self.any_hit(&shadow, 0.0, f64::INFINITY) {
+            return Color::black();
+        }
+        let material = comps.object.material();
+        let color = material.color_at(&comps.surface(), &comps.over_point);
+        let light = Light::new(comps.over_point + sun.direction, sun.intensity);
+        direct_lightning(
+            &material,
+            &color,
+            &light,
+            &comps.over_point,
+            &comps.eyev,
+            &comps.normalv,
+        )
     }
-    pub fn color_at(&self, r: &Ray) -> Tuple {
-        if let Some(i) = hit(intersect_world(self, r)) {
-            let comps = Computation::new(&i, r, &intersect_world(self, r));
-            return self.shade_hit(&comps);
+
+    // direct light from the environment map, one importance-sampled direction
+    // per (u, v) pair, each with a shadow ray out of the scene
+    pub fn environment_lighting(&self, comps: &Computation, samples: &[(f64, f64)]) -> Color {
+        let Some(environment) = &self.environment else {
+            return Color::black();
+        };
+        let material = comps.object.material();
+        let color = material.color_at(&comps.surface(), &comps.over_point);
+        let mut out = Color::black();
+        for (u, v) in samples {
+            let Some((direction, radiance, pdf)) = environment.sample(*u, *v) else {
+                break;
+            };
+            if pdf <= 0.0 || direction.dot(&comps.normalv) <= 0.0 {
+                continue;
+            }
+            let shadow = Ray::with_time(comps.over_point, direction, comps.time);
+            if self.any_hit(&shadow, 0.0, f64::INFINITY) {
+                continue;
+            }
+            // a point light one unit away whose intensity stands in for the
+            // radiance over the solid angle this sample covers
+            let weight = 1.0 / (PI * pdf * samples.len() as f64);
+            let light = Light::new(comps.over_point + direction, radiance * weight);
+            out = out
+                + direct_lightning(
+                    &material,
+                    &color,
+                    &light,
+                    &comps.over_point,
+                    &comps.eyev,
+                    &comps.normalv,
+                );
         }
-        Tuple::default_color()
+        out
     }
 
-    pub fn is_shadowed(&self, point: &Tuple) -> bool {
-        let v = self.light.position - *point;
-        let distance = v.magnitude();
-        let direction = v.normalize();
-        let r = Ray::new(*point, direction);
-        let intersections = intersect_world(self, &r);
-        if let Some(h) = hit(intersections) {
-            if h.t < distance {
-                return true;
+    // direct light from every emissive shape, estimated with one sample per
+    // (u, v) pair; each sample acts as a small point light on the surface
+    pub fn emitter_lighting(&self, comps: &Computation, samples: &[(f64, f64)]) -> Color {
+        let material = comps.object.material();
+        let color = material.color_at(&comps.surface(), &comps.over_point);
+        let mut out = Color::black();
+        for emitter in &self.shapes {
+            if std::ptr::eq(emitter, comps.object) || !emitter.material().is_emissive() {
+                continue;
+            }
+            let emission = emitter.material().emission();
+            for (u, v) in samples {
+                let Some((point, normal, area)) = emitter.sample_surface(*u, *v) else {
+                    break;
+                };
+                let to_light = point - comps.over_point;
+                let distance = to_light.magnitude();
+                let cos_light = normal.dot(&(to_light / distance)).abs();
+                if cos_light == 0.0 || self.is_occluded(&comps.over_point, &point, comps.time) {
+                    continue;
+                }
+                let weight = cos_light * area / (distance * distance * PI * samples.len() as f64);
+                let light = Light::new(point, emission * weight);
+                out = out
+                    + direct_lightning(
+                        &material,
+                        &color,
+                        &light,
+                        &comps.over_point,
+                        &comps.eyev,
+                        &comps.normalv,
+                    );
             }
         }
-        false
+        out
     }
-    pub fn reflected_color(&self, comps: &Computation) -> Tuple {
-        if comps.object.material().reflective == 0.0 {
-            return Tuple::default_color();
+    pub fn color_at(&self, r: &Ray, remaining: u32) -> Color {
+        self.color_for_hit(r, self.closest_hit(r, 0.0, f64::INFINITY), remaining)
+    }
+
+    // `color_at` for a ray whose closest hit is already known, e.g. from a
+    // packet of camera rays
+    pub fn color_for_hit(&self, r: &Ray, hit: Option<Intersection<'_>>, remaining: u32) -> Color {
+        let Some(i) = hit else {
+            return self.background.at(&r.direction);
+        };
+        // the full list is only needed to find n1 and n2, which only matter
+        // where light passes through the surface
+        let xs = if i.object.primitive().material().transparency > 0.0 {
+            intersect_world(self, r)
+        } else {
+            vec![]
+        };
+        self.shade_hit(&Computation::new(&i, r, &xs), remaining)
+    }
+
+    // the nearest intersection with t strictly between `t_min` and `t_max`;
+    // over (0, inf) it is the same as `hit(intersect_world(..))` without
+    // collecting and sorting every intersection along the ray
+    pub fn closest_hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<Intersection<'_>> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        for shape in &self.shapes {
+            if let Some(t) = shape.closest_t(r, t_min, t_max) {
+                t_max = t;
+                closest = Some(Intersection::new(t, shape));
+            }
+        }
+        closest
+    }
+
+    // whether anything crosses the ray between `t_min` and `t_max`, stopping
+    // at the first shape that does
+    pub fn any_hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.shapes
+            .iter()
+            .any(|shape| shape.closest_t(r, t_min, t_max).is_some())
+    }
+
+    pub fn is_shadowed(&self, point: &Point3) -> bool {
+        self.is_occluded(point, &self.light.position, 0.0)
+    }
+
+    // whether anything lies between `point` and `target`; surfaces right at
+    // the target, like the emitter a sample was taken on, do not count
+    pub fn is_occluded(&self, point: &Point3, target: &Point3, time: f64) -> bool {
+        let v = *target - *point;
+        let distance = v.magnitude();
+        let direction = v.normalize();
+        let r = Ray::with_time(*point, direction, time);
+        self.any_hit(&r, 0.0, distance - surface_offset(target))
+    }
+    pub fn reflected_color(&self, comps: &Computation, remaining: u32) -> Color {
+        let material = comps.object.material();
+        if material.reflective == 0.0 || remaining == 0 {
+            return Color::black();
         }
-        let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
-        let color = self.color_at(&reflect_ray);
-        color * comps.object.material().reflective
+        let color = self.glossy_color(
+            &comps.over_point,
+            &comps.reflectv,
+            &comps.normalv,
+            material.blur,
+            comps.time,
+            remaining,
+        );
+        color * material.reflective
     }
-    pub fn refracted_color(&self, comps: &Computation, remaining: u32) -> Tuple {
-        if comps.object.material().transparency == 0.0 || remaining == 0 {
-            return Tuple::color(0.0, 0.0, 0.0);
+    pub fn refracted_color(&self, comps: &Computation, remaining: u32) -> Color {
+        let material = comps.object.material();
+        if material.transparency == 0.0 || remaining == 0 {
+            return Color::new(0.0, 0.0, 0.0);
         }
         let n_ratio = comps.n1 / comps.n2;
         let cos_i = comps.eyev.dot(&comps.normalv);
         let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
         if sin2_t > 1.0 {
-            return Tuple::color(0.0, 0.0, 0.0);
+            return Color::new(0.0, 0.0, 0.0);
         }
         let cos_t = (1.0 - sin2_t).sqrt();
         let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
-        let refract_ray = Ray::new(comps.under_point, direction);
-        self.color_at(&refract_ray) * comps.object.material().transparency
+        let color = self.glossy_color(
+            &comps.under_point,
+            &direction,
+            &-comps.normalv,
+            material.blur,
+            comps.time,
+            remaining,
+        );
+        color * material.transparency
+    }
+
+    // the light arriving at `origin` from around `direction`, traced with one
+    // bounce less than `remaining`. A rough surface hit straight from the
+    // camera averages a grid of rays over its lobe, each kept on the `side`
+    // of the surface it was meant to leave by; deeper down only the single
+    // ray along `direction` is followed, so rough surfaces facing each other
+    // cost a bounded number of rays.
+    fn glossy_color(
+        &self,
+        origin: &Point3,
+        direction: &Vector3,
+        side: &Vector3,
+        blur: f64,
+        time: f64,
+        remaining: u32,
+    ) -> Color {
+        if blur <= 0.0 || remaining < self.max_depth {
+            return self.color_at(&Ray::with_time(*origin, *direction, time), remaining - 1);
+        }
+        let samples = sample_grid(self.glossy_samples.max(1));
+        let mut sum = Color::black();
+        for (u, v) in &samples {
+            let d = fold_above(&glossy_lobe(direction, blur, *u, *v), side);
+            sum = sum + self.color_at(&Ray::with_time(*origin, d, time), remaining - 1);
+        }
+        sum / samples.len() as f64
     }
 }
 
-pub fn intersect_world(world: &World, ray: &Ray) -> Vec<Intersection> {
+// n * n points spread evenly over the unit square
+fn sample_grid(n: usize) -> Vec<(f64, f64)> {
+    let mut grid = Vec::with_capacity(n * n);
+    for ix in 0..n {
+        for jx in 0..n {
+            grid.push(((ix as f64 + 0.5) / n as f64, (jx as f64 + 0.5) / n as f64));
+        }
+    }
+    grid
+}
+
+pub fn intersect_world<'a>(world: &'a World, ray: &Ray) -> Vec<Intersection<'a>> {
     let mut out = vec![];
-    for ix in 0..world.shapes.len() {
-        let mut xs = world.shapes[ix].intersect(ray);
+    for shape in &world.shapes {
+        let mut xs = shape.intersect(ray);
         out.append(&mut xs);
     }
     intersections(&mut out)
 }
 impl Default for World {
     fn default() -> Self {
-        let light = Light::new(
-            Tuple::point(-10.0, 10.0, -10.0),
-            Tuple::color(1.0, 1.0, 1.0),
-        );
+        let light = Light::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let mut s1 = Shape::Sphere(Sphere::new());
         let mut material = Material::new();
-        material.color = Tuple::color(0.8, 1.0, 0.6);
+        material.color = Color::new(0.8, 1.0, 0.6);
         material.diffuse = 0.7;
         material.specular = 0.2;
         s1.set_material(material);
         let mut s2 = Shape::Sphere(Sphere::new());
-        s2.set_transform(scaling(0.5, 0.5, 0.5));
+        s2.set_transform(scaling(0.5, 0.5, 0.5))
+            .expect("a uniform scale is invertible");
         let shapes = &[s1, s2];
         Self::new(light, shapes)
     }
@@ -114,13 +386,13 @@ impl Default for World {
 #[cfg(test)]
 mod world_tests {
     use crate::features::{
-        intersections::{computations::Computation, intersections, Intersection},
+        intersections::{computations::Computation, hit, intersections, Intersection},
         lights::Light,
         rays::Ray,
-        shape::Shape,
+        shape::{Primitive, Shape},
         spheres::Sphere,
         transformations::translation,
-        tuple::Tuple,
+        tuple::{Color, Point3, Vector3},
     };
 
     use super::{intersect_world, World};
@@ -128,17 +400,14 @@ mod world_tests {
     #[test]
     fn testing_default_world() {
         let d_w = World::default();
-        let light = Light::new(
-            Tuple::point(-10.0, 10.0, -10.0),
-            Tuple::color(1.0, 1.0, 1.0),
-        );
+        let light = Light::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
         assert_eq!(d_w.light, light);
     }
 
     #[test]
     fn testing_intersect_world() {
         let w = World::default();
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let xs = intersect_world(&w, &r);
         assert_eq!(xs.len(), 4);
         assert_eq!(xs[0].t, 4.0);
@@ -147,67 +416,148 @@ mod world_tests {
         assert_eq!(xs[3].t, 6.0);
     }
 
+    #[test]
+    fn intersections_name_shapes_in_the_arena() {
+        let mut w = World::default();
+        // an exact copy of the outer default sphere
+        let twin = w.add(w.shapes()[0].clone());
+        assert_eq!(w.shape(twin), &w.shapes()[0]);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = intersect_world(&w, &r);
+        assert_eq!(xs.len(), 6);
+        let ids: Vec<_> = xs.iter().map(|i| w.id_of(i.object).unwrap()).collect();
+        let outer = w.ids().next().unwrap();
+        assert_eq!(ids.iter().filter(|&&id| id == outer).count(), 2);
+        assert_eq!(ids.iter().filter(|&&id| id == twin).count(), 2);
+        assert_eq!(w.id_of(&w.shapes()[0].clone()), None);
+    }
+
+    #[test]
+    fn closest_hit_agrees_with_hit() {
+        let w = World::default();
+        for (origin, direction) in [
+            (Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Point3::new(0.0, 0.0, 0.75), Vector3::new(0.0, 0.0, 1.0)),
+            (Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Point3::new(0.0, 2.0, -5.0), Vector3::new(0.0, 0.0, 1.0)),
+        ] {
+            let r = Ray::new(origin, direction);
+            let expected = hit(intersect_world(&w, &r));
+            assert_eq!(w.closest_hit(&r, 0.0, f64::INFINITY), expected);
+        }
+    }
+
+    #[test]
+    fn closest_hit_and_any_hit_respect_the_bounds() {
+        let w = World::default();
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        // crossings at 4, 4.5, 5.5 and 6
+        let i = w.closest_hit(&r, 4.0, 10.0).unwrap();
+        assert_eq!(i.t, 4.5);
+        assert!(i.is_on(&w.shapes()[1]));
+        assert_eq!(w.closest_hit(&r, 4.5, 5.5), None);
+        assert!(w.any_hit(&r, 0.0, 4.1));
+        assert!(!w.any_hit(&r, 0.0, 4.0));
+        assert!(!w.any_hit(&r, 6.0, f64::INFINITY));
+    }
+
     #[test]
     fn testing_shading_intersection() {
         let w = World::default();
-        let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
         let shape = w.shapes[0].clone();
-        let i = Intersection::new(4.0, shape);
+        let i = Intersection::new(4.0, &shape);
         let comps = Computation::new(&i, &r, &[]);
-        let c = w.shade_hit(&comps);
-        assert_eq!(c, Tuple::color(0.38066, 0.47583, 0.2855))
+        let c = w.shade_hit(&comps, 5);
+        assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855))
     }
 
     #[test]
     fn testing_shading_intersection_inside() {
         let mut w = World::default();
         w.set_light(Light::new(
-            Tuple::point(0.0, 0.25, 0.0),
-            Tuple::color(1.0, 1.0, 1.0),
+            Point3::new(0.0, 0.25, 0.0),
+            Color::new(1.0, 1.0, 1.0),
         ));
-        let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
         let shape = w.shapes[1].clone();
-        let i = Intersection::new(0.5, shape);
+        let i = Intersection::new(0.5, &shape);
         let comps = Computation::new(&i, &r, &[]);
-        let c = w.shade_hit(&comps);
-        assert_eq!(c, Tuple::color(0.9049522, 0.9049522, 0.9049522))
+        let c = w.shade_hit(&comps, 5);
+        assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498))
     }
     #[test]
     fn testing_shade_hit_given_intersection_in_shadow() {
         let mut w = World::default();
         w.set_light(Light::new(
-            Tuple::point(0.0, 0.0, -10.0),
-            Tuple::color(1.0, 1.0, 1.0),
+            Point3::new(0.0, 0.0, -10.0),
+            Color::new(1.0, 1.0, 1.0),
         ));
         let s1 = Shape::Sphere(Sphere::new());
         let mut s2 = Shape::Sphere(Sphere::new());
-        s2.set_transform(translation(0.0, 0.0, 10.0));
+        s2.set_transform(translation(0.0, 0.0, 10.0)).unwrap();
         w.shapes = vec![s1, s2.clone()];
-        let r = Ray::new(Tuple::point(0.0, 0.0, 5.0), Tuple::vector(0.0, 0.0, 1.0));
-        let i = Intersection::new(4.0, s2);
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &s2);
         let comps = Computation::new(&i, &r, &[]);
-        let c = w.shade_hit(&comps);
-        assert_eq!(c, Tuple::color(0.1, 0.1, 0.1));
+        let c = w.shade_hit(&comps, 5);
+        assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
 
     #[cfg(test)]
     mod world_color_tests {
-        use crate::features::{rays::Ray, tuple::Tuple, world::World};
+        use crate::features::{
+            background::Background,
+            rays::Ray,
+            shape::Shape,
+            spheres::Sphere,
+            tuple::{Color, Point3, Vector3},
+            world::World,
+        };
 
         #[test]
         fn testing_color_ray_miss() {
             let w = World::default();
-            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 1.0, 0.0));
-            let c = w.color_at(&r);
-            assert_eq!(c, Tuple::color(0.0, 0.0, 0.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 1.0, 0.0));
+            let c = w.color_at(&r, 5);
+            assert_eq!(c, Color::new(0.0, 0.0, 0.0));
+        }
+
+        #[test]
+        fn missed_rays_see_the_background() {
+            let w = World {
+                background: Background::Gradient {
+                    bottom: Color::new(0.0, 0.0, 0.0),
+                    top: Color::new(0.2, 0.4, 0.6),
+                },
+                ..World::default()
+            };
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 1.0, 0.0));
+            assert_eq!(w.color_at(&r, 5), Color::new(0.2, 0.4, 0.6));
+        }
+
+        #[test]
+        fn mirrors_reflect_the_background() {
+            let mut mirror = Sphere::new();
+            mirror.material.color = Color::black();
+            mirror.material.ambient = 0.0;
+            mirror.material.diffuse = 0.0;
+            mirror.material.specular = 0.0;
+            mirror.material.reflective = 1.0;
+            let mut w = World::new(World::default().light, &[Shape::Sphere(mirror)]);
+            w.background = Background::Solid(Color::new(0.25, 0.5, 0.75));
+            // straight back off the front of the sphere, missing it on the way
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+            assert_eq!(w.color_at(&r, 5), Color::new(0.25, 0.5, 0.75));
         }
 
         #[test]
         fn testing_color_ray_hits() {
             let w = World::default();
-            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
-            let c = w.color_at(&r);
-            assert_eq!(c, Tuple::color(0.38066, 0.47583, 0.2855));
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+            let c = w.color_at(&r, 5);
+            assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
         }
 
         // #[test]
@@ -215,8 +565,8 @@ mod world_tests {
         //     let mut w = World::default();
         //     w.shapes[0].material.ambient = 1.0;
         //     w.shapes[1].material.ambient = 1.0;
-        //     let r = Ray::new(Tuple::point(0.0, 0.0, 0.75), Tuple::vector(0.0, 0.0, -1.0));
-        //     let c = w.color_at(&r);
+        //     let r = Ray::new(Point3::new(0.0, 0.0, 0.75), Vector3::new(0.0, 0.0, -1.0));
+        //     let c = w.color_at(&r, 5);
         //     assert_eq!(c, w.shapes[1].material.color);
         // }
     }
@@ -227,154 +577,304 @@ mod world_tests {
         #[test]
         fn testing_no_shadow() {
             let world = World::default();
-            let point = Tuple::point(0.0, 10.0, 0.0);
+            let point = Point3::new(0.0, 10.0, 0.0);
             assert!(!world.is_shadowed(&point))
         }
 
         #[test]
         fn testing_object_between_point_light() {
             let world = World::default();
-            let point = Tuple::point(10.0, -10.0, 10.0);
+            let point = Point3::new(10.0, -10.0, 10.0);
             assert!(world.is_shadowed(&point))
         }
 
         #[test]
         fn testing_object_behind_light() {
             let world = World::default();
-            let point = Tuple::point(-20.0, 10.0, -20.0);
+            let point = Point3::new(-20.0, 10.0, -20.0);
             assert!(!world.is_shadowed(&point))
         }
         #[test]
         fn testing_object_behind_point() {
             let world = World::default();
-            let point = Tuple::point(-2.0, 2.0, -2.0);
+            let point = Point3::new(-2.0, 2.0, -2.0);
             assert!(!world.is_shadowed(&point))
         }
     }
     #[cfg(test)]
     mod reflected_color_tests {
-        use crate::features::planes::Plane;
+        use crate::features::{background::Background, planes::Plane};
 
         use super::*;
         #[test]
         fn testing_reflected_color_for_nonreflective_material() {
             let mut world = World::default();
-            let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 0.0, 1.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
             world.shapes[1].set_material_ambient(1.0);
-            let i = Intersection::new(1.0, world.shapes[1].clone());
+            let i = Intersection::new(1.0, &world.shapes[1]);
             let comps = Computation::new(&i, &r, &[]);
-            let color = world.reflected_color(&comps);
-            assert_eq!(color, Tuple::color(0.0, 0.0, 0.0))
+            let color = world.reflected_color(&comps, 5);
+            assert_eq!(color, Color::new(0.0, 0.0, 0.0))
         }
         #[test]
         fn testing_reflected_color_for_reflective_material() {
             let mut world = World::default();
             let mut plane = Plane::new();
             plane.material.reflective = 0.5;
-            plane.transform = translation(0.0, -1.0, 0.0);
+            plane.set_transform(translation(0.0, -1.0, 0.0)).unwrap();
             let shape = Shape::Plane(plane);
             world.shapes.push(shape.clone());
             let r = Ray::new(
-                Tuple::point(0.0, 0.0, -3.0),
-                Tuple::vector(0.0, -(2.0_f32.sqrt() / 2.0), 2.0_f32.sqrt() / 2.0),
+                Point3::new(0.0, 0.0, -3.0),
+                Vector3::new(0.0, -(2.0_f64.sqrt() / 2.0), 2.0_f64.sqrt() / 2.0),
             );
-            let i = Intersection::new(2.0_f32.sqrt(), shape);
+            let i = Intersection::new(2.0_f64.sqrt(), &shape);
             let comps = Computation::new(&i, &r, &[]);
-            let color = world.reflected_color(&comps);
-            assert_eq!(color, Tuple::color(0.19032, 0.2379, 0.14274));
+            let color = world.reflected_color(&comps, 5);
+            assert_eq!(color, Color::new(0.19033, 0.23791, 0.14275));
         }
         #[test]
         fn testing_shade_hit_with_reflective_material() {
             let mut world = World::default();
             let mut shape = Plane::new();
             shape.material.reflective = 0.5;
-            shape.transform = translation(0.0, -1.0, 0.0);
-            world.shapes.push(Shape::Plane(shape.clone()));
+            shape.set_transform(translation(0.0, -1.0, 0.0)).unwrap();
+            let id = world.add(Shape::Plane(shape));
             let r = Ray::new(
-                Tuple::point(0.0, 0.0, -3.0),
-                Tuple::vector(0.0, -(2.0_f32.sqrt() / 2.0), 2.0_f32.sqrt() / 2.0),
+                Point3::new(0.0, 0.0, -3.0),
+                Vector3::new(0.0, -(2.0_f64.sqrt() / 2.0), 2.0_f64.sqrt() / 2.0),
             );
-            let i = Intersection::new(2.0_f32.sqrt(), Shape::Plane(shape));
+            let i = Intersection::new(2.0_f64.sqrt(), world.shape(id));
             let comps = Computation::new(&i, &r, &[]);
-            let color = world.shade_hit(&comps);
-            assert_eq!(color, Tuple::color(0.87677, 0.92436, 0.82918));
+            let color = world.shade_hit(&comps, 5);
+            assert_eq!(color, Color::new(0.87676, 0.92434, 0.82917));
         }
+        #[test]
+        fn rough_mirrors_blur_what_they_reflect() {
+            let mut floor = Plane::new();
+            floor.material.reflective = 1.0;
+            let mut w = World::new(World::default().light, &[]);
+            w.background = Background::Gradient {
+                bottom: Color::black(),
+                top: Color::new(1.0, 1.0, 1.0),
+            };
+            let mut reflected = |w: &World, blur: f64| {
+                floor.material.blur = blur;
+                let shape = Shape::Plane(floor.clone());
+                let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+                let i = Intersection::new(1.0, &shape);
+                w.reflected_color(&Computation::new(&i, &r, &[]), w.max_depth)
+            };
+            // straight up is the brightest part of the sky
+            assert_eq!(reflected(&w, 0.0), Color::new(1.0, 1.0, 1.0));
+            let brushed = reflected(&w, 0.5);
+            assert!(brushed.r < 0.99 && brushed.r > 0.5);
+            w.glossy_samples = 8;
+            assert!(reflected(&w, 0.5).r < 0.99);
+        }
+
         #[test]
         fn testing_color_at_with_mutually_reflective_surfaces() {
             let mut world = World {
-                light: Light::new(Tuple::point(0.0, 0.0, 0.0), Tuple::color(1.0, 1.0, 1.0)),
+                light: Light::new(Point3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)),
                 ..Default::default()
             };
             let mut lower = Plane::new();
             lower.material.reflective = 1.0;
-            lower.transform = translation(0.0, -1.0, 0.0);
+            lower.set_transform(translation(0.0, -1.0, 0.0)).unwrap();
             world.shapes.push(Shape::Plane(lower.clone()));
             let mut upper = Plane::new();
             upper.material.reflective = 1.0;
-            upper.transform = translation(0.0, 1.0, 0.0);
+            upper.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
             world.shapes.push(Shape::Plane(upper.clone()));
-            let r = Ray::new(Tuple::point(0.0, 0.0, 0.0), Tuple::vector(0.0, 1.0, 0.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
 
-            world.color_at(&r);
+            world.color_at(&r, 5);
+        }
+
+        #[test]
+        fn rough_mirrors_facing_each_other_stop_at_max_depth() {
+            let mut world = World::new(
+                Light::new(Point3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)),
+                &[],
+            );
+            for y in [-1.0, 1.0] {
+                let mut mirror = Plane::new();
+                mirror.material.reflective = 1.0;
+                mirror.material.blur = 0.5;
+                mirror.set_transform(translation(0.0, y, 0.0)).unwrap();
+                world.add(Shape::Plane(mirror));
+            }
+            world.glossy_samples = 8;
+            world.max_depth = 10;
+            let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+            let c = world.color_at(&r, world.max_depth);
+            assert!(c.r.is_finite() && c.r > 0.0);
+        }
+
+        #[test]
+        fn reflected_color_at_max_recursive_depth() {
+            let mut world = World::default();
+            let mut plane = Plane::new();
+            plane.material.reflective = 0.5;
+            plane.set_transform(translation(0.0, -1.0, 0.0)).unwrap();
+            let id = world.add(Shape::Plane(plane));
+            let r = Ray::new(
+                Point3::new(0.0, 0.0, -3.0),
+                Vector3::new(0.0, -(2.0_f64.sqrt() / 2.0), 2.0_f64.sqrt() / 2.0),
+            );
+            let i = Intersection::new(2.0_f64.sqrt(), world.shape(id));
+            let comps = Computation::new(&i, &r, &[]);
+            assert_eq!(world.reflected_color(&comps, 0), Color::black());
         }
     }
 
     #[cfg(test)]
     mod refracted_color_tests {
-        use crate::features::patterns::{Checker, Pattern, Stripe};
+        use crate::features::{
+            background::Background,
+            patterns::{Pattern, TestPattern},
+            planes::Plane,
+        };
 
         use super::*;
         #[test]
         fn test_refracted_color_with_opaque_surface() {
             let w = World::default();
             let shape = &w.shapes[0];
-            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
-            let xs = intersections(&mut [
-                Intersection::new(4.0, shape.clone()),
-                Intersection::new(6.0, shape.clone()),
-            ]);
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+            let xs =
+                intersections(&mut [Intersection::new(4.0, shape), Intersection::new(6.0, shape)]);
             let comps = Computation::new(&xs[0], &r, &xs);
             let c = w.refracted_color(&comps, 5);
-            assert_eq!(c, Tuple::color(0.0, 0.0, 0.0))
+            assert_eq!(c, Color::new(0.0, 0.0, 0.0))
         }
 
         #[test]
         fn test_refracted_color_at_max_recursive_depth() {
             let mut w = World::default();
-            let shape = &mut w.shapes[0];
-            let mut m = shape.material();
+            let mut m = w.shapes[0].material();
             m.transparency = 1.0;
             m.refractive_index = 1.5;
-            shape.set_material(m);
-            let r = Ray::new(Tuple::point(0.0, 0.0, -5.0), Tuple::vector(0.0, 0.0, 1.0));
-            let xs = intersections(&mut [
-                Intersection::new(4.0, shape.clone()),
-                Intersection::new(6.0, shape.clone()),
-            ]);
+            w.shapes[0].set_material(m);
+            let shape = &w.shapes[0];
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+            let xs =
+                intersections(&mut [Intersection::new(4.0, shape), Intersection::new(6.0, shape)]);
             let comps = Computation::new(&xs[0], &r, &xs);
             let c = w.refracted_color(&comps, 0);
-            assert_eq!(c, Tuple::color(0.0, 0.0, 0.0))
+            assert_eq!(c, Color::new(0.0, 0.0, 0.0))
         }
 
         #[test]
         fn test_refracted_color_under_total_internal_reflection() {
             let mut w = World::default();
-            let shape = &mut w.shapes[0];
-            let mut m = shape.material();
+            let mut m = w.shapes[0].material();
             m.transparency = 1.0;
             m.refractive_index = 1.5;
-            shape.set_material(m);
+            w.shapes[0].set_material(m);
+            let shape = &w.shapes[0];
             let r = Ray::new(
-                Tuple::point(0.0, 0.0, 2_f32.sqrt() / 2.0),
-                Tuple::vector(0.0, 1.0, 0.0),
+                Point3::new(0.0, 0.0, 2_f64.sqrt() / 2.0),
+                Vector3::new(0.0, 1.0, 0.0),
             );
             let xs = intersections(&mut [
-                Intersection::new(-(2_f32.sqrt() / 2.0), shape.clone()),
-                Intersection::new(2_f32.sqrt() / 2.0, shape.clone()),
+                Intersection::new(-(2_f64.sqrt() / 2.0), shape),
+                Intersection::new(2_f64.sqrt() / 2.0, shape),
             ]);
             let comps = Computation::new(&xs[1], &r, &xs);
             let c = w.refracted_color(&comps, 5);
-            assert_eq!(c, Tuple::color(0.0, 0.0, 0.0))
+            assert_eq!(c, Color::new(0.0, 0.0, 0.0))
+        }
+
+        #[test]
+        fn frosted_glass_blurs_what_is_behind_it() {
+            let mut pane = Plane::new();
+            pane.material.transparency = 1.0;
+            let mut w = World::new(World::default().light, &[]);
+            w.background = Background::Gradient {
+                bottom: Color::new(1.0, 1.0, 1.0),
+                top: Color::black(),
+            };
+            let mut refracted = |blur: f64| {
+                pane.material.blur = blur;
+                let shape = Shape::Plane(pane.clone());
+                let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+                let i = Intersection::new(1.0, &shape);
+                w.refracted_color(&Computation::new(&i, &r, &[]), 5)
+            };
+            assert_eq!(refracted(0.0), Color::new(1.0, 1.0, 1.0));
+            let frosted = refracted(0.5);
+            assert!(frosted.r < 0.99 && frosted.r > 0.5);
+        }
+
+        fn glass_floor_over_ball(reflective: f64) -> World {
+            let mut w = World::default();
+            let mut floor = Plane::new();
+            floor.set_transform(translation(0.0, -1.0, 0.0)).unwrap();
+            floor.material.reflective = reflective;
+            floor.material.transparency = 0.5;
+            floor.material.refractive_index = 1.5;
+            w.add(Shape::Plane(floor));
+            let mut ball = Sphere::new();
+            ball.material.color = Color::new(1.0, 0.0, 0.0);
+            ball.material.ambient = 0.5;
+            ball.set_transform(translation(0.0, -3.5, -0.5)).unwrap();
+            w.add(Shape::Sphere(ball));
+            w
+        }
+
+        #[test]
+        fn shade_hit_with_transparent_material() {
+            let w = glass_floor_over_ball(0.0);
+            let r = Ray::new(
+                Point3::new(0.0, 0.0, -3.0),
+                Vector3::new(0.0, -(2.0_f64.sqrt() / 2.0), 2.0_f64.sqrt() / 2.0),
+            );
+            let xs = intersections(&mut [Intersection::new(2.0_f64.sqrt(), &w.shapes[2])]);
+            let comps = Computation::new(&xs[0], &r, &xs);
+            assert_eq!(
+                w.shade_hit(&comps, 5),
+                Color::new(0.93642, 0.68642, 0.68642)
+            );
+        }
+
+        #[test]
+        fn shade_hit_blends_reflection_and_refraction_by_schlick() {
+            let w = glass_floor_over_ball(0.5);
+            let r = Ray::new(
+                Point3::new(0.0, 0.0, -3.0),
+                Vector3::new(0.0, -(2.0_f64.sqrt() / 2.0), 2.0_f64.sqrt() / 2.0),
+            );
+            let xs = intersections(&mut [Intersection::new(2.0_f64.sqrt(), &w.shapes[2])]);
+            let comps = Computation::new(&xs[0], &r, &xs);
+            assert_eq!(
+                w.shade_hit(&comps, 5),
+                Color::new(0.93391, 0.69643, 0.69243)
+            );
+        }
+
+        #[test]
+        fn frosted_glass_shows_through_color_at() {
+            let seen = |blur: f64| {
+                let mut pane = Plane::new();
+                pane.material.color = Color::black();
+                pane.material.ambient = 0.0;
+                pane.material.diffuse = 0.0;
+                pane.material.specular = 0.0;
+                pane.material.transparency = 1.0;
+                pane.material.blur = blur;
+                let mut w = World::new(World::default().light, &[Shape::Plane(pane)]);
+                w.background = Background::Gradient {
+                    bottom: Color::new(1.0, 1.0, 1.0),
+                    top: Color::black(),
+                };
+                let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+                w.color_at(&r, w.max_depth)
+            };
+            assert_eq!(seen(0.0), Color::new(1.0, 1.0, 1.0));
+            let frosted = seen(0.5);
+            assert!(frosted.r < 0.99 && frosted.r > 0.5);
         }
 
         #[test]
@@ -383,7 +883,7 @@ mod world_tests {
             let mut a = w.shapes[0].clone();
             let mut m = a.material();
             m.ambient = 1.0;
-            m.pattern = Some(Pattern::Stripe(default_pattern()));
+            m.pattern = Some(Pattern::Test(TestPattern::new()));
             a.set_material(m);
             let mut b = w.shapes[1].clone();
             m = b.material();
@@ -392,21 +892,277 @@ mod world_tests {
             b.set_material(m);
             w.shapes[0] = a.clone();
             w.shapes[1] = b.clone();
-            let r = Ray::new(Tuple::point(0.0, 0.0, 0.1), Tuple::vector(0.0, 1.0, 0.0));
+            let r = Ray::new(Point3::new(0.0, 0.0, 0.1), Vector3::new(0.0, 1.0, 0.0));
             let xs = intersections(&mut [
-                Intersection::new(-0.9899, a.clone()),
-                Intersection::new(-0.4899, b.clone()),
-                Intersection::new(0.4899, b.clone()),
-                Intersection::new(0.9899, a.clone()),
+                Intersection::new(-0.9899, &a),
+                Intersection::new(-0.4899, &b),
+                Intersection::new(0.4899, &b),
+                Intersection::new(0.9899, &a),
             ]);
-            let comps = Computation::new(&xs[1], &r, &xs);
+            let comps = Computation::new(&xs[2], &r, &xs);
             let c = w.refracted_color(&comps, 5);
-            assert_eq!(c, Tuple::color(0.0, 0.99888, 0.04725));
+            assert_eq!(c, Color::new(0.0, 0.99888, 0.04722));
         }
-        fn default_pattern() -> Stripe {
-            let white: Tuple = Tuple::color(1.0, 1.0, 1.0);
-            let black: Tuple = Tuple::color(0.0, 0.0, 0.0);
-            Stripe::new(white, black)
+    }
+}
+
+#[cfg(test)]
+mod emitter_tests {
+    use crate::features::{
+        intersections::{computations::Computation, Intersection},
+        lights::Light,
+        planes::Plane,
+        quads::Quad,
+        rays::Ray,
+        shape::{Primitive, Shape},
+        spheres::Sphere,
+        transformations::{scaling, translation},
+        tuple::{Color, Point3, Vector3},
+    };
+
+    use super::World;
+
+    fn dark_light() -> Light {
+        Light::new(Point3::new(0.0, 10.0, -10.0), Color::new(0.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn emissive_surface_appears_bright() {
+        let mut panel = Quad::new();
+        panel
+            .set_transform(translation(0.0, 0.0, 2.0) * scaling(3.0, 1.0, 3.0))
+            .unwrap();
+        panel.material.emissive = Color::new(1.0, 0.5, 0.25);
+        panel.material.emissive_strength = 2.0;
+        let world = World::new(dark_light(), &[Shape::Quad(panel)]);
+        let r = Ray::new(Point3::new(0.0, 1.0, 2.0), Vector3::new(0.0, -1.0, 0.0));
+        assert_eq!(world.color_at(&r, 5), Color::new(2.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn glowing_panel_lights_the_floor() {
+        let floor = Plane::new();
+        let mut panel = Quad::new();
+        panel
+            .set_transform(translation(0.0, 3.0, 0.0) * scaling(0.5, 1.0, 0.5))
+            .unwrap();
+        panel.material.emissive = Color::new(1.0, 1.0, 1.0);
+        panel.material.emissive_strength = 10.0;
+        let world = World::new(
+            dark_light(),
+            &[Shape::Plane(floor.clone()), Shape::Quad(panel)],
+        );
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let lit = world.color_at(&r, 5);
+        assert!(lit.r > 0.0);
+        assert_eq!(lit.r, lit.g);
+
+        let r = Ray::new(
+            Point3::new(20.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let far = world.color_at(&r, 5);
+        assert!(far.r < lit.r);
+    }
+
+    #[test]
+    fn small_spherical_emitter_matches_analytic_irradiance() {
+        let floor = Plane::new();
+        let mut bulb = Sphere::new();
+        bulb.set_transform(translation(0.0, 5.0, 0.0)).unwrap();
+        bulb.material.emissive = Color::new(1.0, 1.0, 1.0);
+        bulb.material.emissive_strength = 10.0;
+        let mut world = World::new(
+            dark_light(),
+            &[Shape::Plane(floor.clone()), Shape::Sphere(bulb)],
+        );
+        world.area_light_samples = 32;
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let i = Intersection::new(2.0_f64.sqrt(), &world.shapes()[0]);
+        let comps = Computation::new(&i, &r, &[]);
+        let c = world.shade_hit(&comps, 5);
+        // a lambertian floor under a sphere of radiance L: albedo * L * r^2 / d^2
+        let expected = 0.9 * 10.0 / 25.0;
+        assert!((c.r - expected).abs() < expected * 0.05);
+    }
+
+    #[test]
+    fn occluded_emitter_casts_shadow() {
+        let floor = Plane::new();
+        let mut panel = Quad::new();
+        panel
+            .set_transform(translation(0.0, 4.0, 0.0) * scaling(0.5, 1.0, 0.5))
+            .unwrap();
+        panel.material.emissive = Color::new(1.0, 1.0, 1.0);
+        let mut blocker = Sphere::new();
+        blocker.set_transform(translation(0.0, 2.0, 0.0)).unwrap();
+        let world = World::new(
+            dark_light(),
+            &[
+                Shape::Plane(floor.clone()),
+                Shape::Quad(panel),
+                Shape::Sphere(blocker),
+            ],
+        );
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let i = Intersection::new(2.0_f64.sqrt(), &world.shapes()[0]);
+        let comps = Computation::new(&i, &r, &[]);
+        assert_eq!(world.shade_hit(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+}
+
+#[cfg(test)]
+mod environment_tests {
+    use std::f64::consts::PI;
+
+    use crate::features::{
+        canvas::Canvas,
+        environment::Environment,
+        intersections::{computations::Computation, Intersection},
+        lights::{Light, Sun},
+        materials::Material,
+        occlusion::AmbientOcclusion,
+        planes::Plane,
+        rays::Ray,
+        shape::{Primitive, Shape},
+        sky::Sky,
+        spheres::Sphere,
+        transformations::translation,
+        tuple::{Color, Point3, Vector3},
+    };
+
+    use super::World;
+
+    fn matte_floor() -> Plane {
+        let mut floor = Plane::new();
+        floor.material = Material::new();
+        floor.material.ambient = 0.0;
+        floor.material.specular = 0.0;
+        floor.material.diffuse = 0.8;
+        floor
+    }
+
+    fn lit_by_white_sky(shapes: &[Shape]) -> World {
+        let mut sky = Canvas::new(32, 16);
+        for row in sky.canvas.iter_mut() {
+            for pixel in row.iter_mut() {
+                *pixel = Color::new(1.0, 1.0, 1.0);
+            }
         }
+        let mut world = World::new(
+            Light::new(Point3::new(0.0, 10.0, 0.0), Color::black()),
+            shapes,
+        );
+        world.environment = Some(Environment::new(sky));
+        world.environment_samples = 24;
+        world
+    }
+
+    #[test]
+    fn uniform_sky_lights_a_floor_by_its_albedo() {
+        let world = lit_by_white_sky(&[Shape::Plane(matte_floor())]);
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let i = Intersection::new(2.0_f64.sqrt(), &world.shapes()[0]);
+        let comps = Computation::new(&i, &r, &[]);
+        // a lambertian surface under radiance L everywhere reflects albedo * L
+        let c = world.shade_hit(&comps, 5);
+        assert!((c.r - 0.8).abs() < 0.8 * 0.05, "{c:?}");
+    }
+
+    #[test]
+    fn roof_shadows_the_sky() {
+        let mut roof = Plane::new();
+        roof.set_transform(translation(0.0, 2.0, 0.0)).unwrap();
+        let world = lit_by_white_sky(&[Shape::Plane(matte_floor()), Shape::Plane(roof)]);
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let i = Intersection::new(2.0_f64.sqrt(), &world.shapes()[0]);
+        let comps = Computation::new(&i, &r, &[]);
+        assert_eq!(world.shade_hit(&comps, 5), Color::black());
+    }
+
+    #[test]
+    fn sun_lights_what_it_can_see() {
+        let sun = Sun::new(Vector3::new(0.0, 1.0, 0.0), Color::new(2.0, 2.0, 2.0));
+        let mut world = World::new(
+            Light::new(Point3::new(0.0, 10.0, 0.0), Color::black()),
+            &[Shape::Plane(matte_floor())],
+        );
+        world.sun = Some(sun);
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let i = Intersection::new(2.0_f64.sqrt(), &world.shapes()[0]);
+        let comps = Computation::new(&i, &r, &[]);
+        assert_eq!(world.shade_hit(&comps, 5), Color::new(1.6, 1.6, 1.6));
+
+        let mut roof = Plane::new();
+        roof.set_transform(translation(0.0, 2.0, 0.0)).unwrap();
+        world.add(Shape::Plane(roof));
+        let i = Intersection::new(2.0_f64.sqrt(), &world.shapes()[0]);
+        let comps = Computation::new(&i, &r, &[]);
+        assert_eq!(world.shade_hit(&comps, 5), Color::black());
+    }
+
+    #[test]
+    fn ambient_occlusion_darkens_only_the_ambient_term() {
+        let mut floor = Plane::new();
+        floor.material.ambient = 1.0;
+        floor.material.diffuse = 0.0;
+        floor.material.specular = 0.0;
+        let mut ball = Sphere::new();
+        ball.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        let mut world = World::new(
+            Light::new(Point3::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0)),
+            &[Shape::Plane(floor), Shape::Sphere(ball)],
+        );
+        let shade_floor_at = |world: &World, x: f64| {
+            let r = Ray::new(Point3::new(x, 1.0, -1.0), Vector3::new(0.0, -1.0, 0.0));
+            let i = Intersection::new(1.0, &world.shapes()[0]);
+            world.shade_hit(&Computation::new(&i, &r, &[]), 5)
+        };
+        assert_eq!(shade_floor_at(&world, 0.5), Color::new(1.0, 1.0, 1.0));
+        world.ambient_occlusion = Some(AmbientOcclusion::new(6, 2.0));
+        assert!(shade_floor_at(&world, 0.5).r < 0.9);
+        assert_eq!(shade_floor_at(&world, 10.0), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn sky_is_background_and_light() {
+        let mut world = World::new(
+            Light::new(Point3::new(0.0, 10.0, 0.0), Color::black()),
+            &[Shape::Plane(matte_floor())],
+        );
+        let sky = Sky::new(PI / 4.0, 0.0, 3.0);
+        world.set_sky(sky.clone());
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        assert_eq!(
+            world.color_at(&Ray::new(Point3::new(0.0, 1.0, 0.0), up), 5),
+            sky.at(&up)
+        );
+        assert_eq!(world.sun, Some(sky.sun()));
+        let r = Ray::new(
+            Point3::new(0.0, 1.0, -1.0),
+            Vector3::new(0.0, -1.0, 1.0).normalize(),
+        );
+        let i = Intersection::new(2.0_f64.sqrt(), &world.shapes()[0]);
+        let comps = Computation::new(&i, &r, &[]);
+        let lit = world.shade_hit(&comps, 5);
+        assert!(lit.luminance() > world.sun_lighting(&comps).luminance());
     }
 }
@@ -0,0 +1,10 @@
+//! A ray tracer following "The Ray Tracer Challenge".
+//!
+//! Everything lives under [`features`]; the [`prelude`] re-exports what a
+//! scene needs, so `use ray_tracer_challenge::prelude::*;` is enough to
+//! build a world, point a camera at it and write the image out.
+
+pub mod features;
+pub mod prelude;
+
+pub use features::error::{Error, Result};
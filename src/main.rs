@@ -1,9 +1,16 @@
-use practice::sphere::draw_sphere;
-
-mod features;
-mod practice;
-fn main() {
-   draw_sphere();
-}
+use std::f64::consts::PI;
 
+use ray_tracer_challenge::prelude::*;
 
+// renders the default world and prints it as a ppm; see `examples/` for
+// fuller scenes
+fn main() -> ray_tracer_challenge::Result<()> {
+    let mut camera = Camera::new(400.0, 200.0, PI / 3.0)?;
+    camera.set_transform(view_transformation(
+        Point3::new(0.0, 1.5, -5.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    ))?;
+    camera.render(&World::default()).to_ppm();
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! The types and functions most scenes need, in one import.
+//!
+//! Items here are re-exported from [`crate::features`] and keep their names;
+//! anything more specialised (packets, animation, stereo rigs, ...) is
+//! reached through its own module.
+
+pub use crate::features::error::Error;
+
+// math
+pub use crate::features::matrice::Matrice4;
+pub use crate::features::transformations::{
+    rotation_x, rotation_y, rotation_z, scaling, shearing, translation, view_transformation,
+};
+pub use crate::features::tuple::{Color, Point3, Vector3, EPSILON};
+
+// geometry
+pub use crate::features::cube::Cube;
+pub use crate::features::intersections::{
+    computations::Computation, hit, intersections, Intersection,
+};
+pub use crate::features::planes::Plane;
+pub use crate::features::quads::Quad;
+pub use crate::features::rays::Ray;
+pub use crate::features::shape::{Primitive, Shape};
+pub use crate::features::spheres::Sphere;
+
+// materials and lights
+pub use crate::features::lights::{Light, Sun};
+pub use crate::features::materials::{Material, ShadingModel};
+pub use crate::features::occlusion::AmbientOcclusion;
+pub use crate::features::patterns::{Blend, Checker, Gradient, Pattern, Ring, Stripe};
+
+// world, camera and canvas
+pub use crate::features::background::{Background, CubeMap};
+pub use crate::features::camera::cameras::Camera;
+pub use crate::features::canvas::Canvas;
+pub use crate::features::environment::Environment;
+pub use crate::features::sky::Sky;
+pub use crate::features::world::{intersect_world, ShapeId, World};